@@ -0,0 +1,58 @@
+use clap::Subcommand;
+use std::error;
+use std::io::Write;
+
+/// Configuration inspection commands
+#[derive(Subcommand)]
+pub enum ConfigSubCommands {
+    /// Print the effective configuration and where each value was read from
+    Show,
+}
+
+/// A resolved configuration value along with its source
+pub struct Setting {
+    pub key: &'static str,
+    pub value: String,
+    pub source: String,
+}
+
+impl Setting {
+    pub fn new(key: &'static str, value: &str, source: &str) -> Self {
+        Setting {
+            key,
+            value: value.to_string(),
+            source: source.to_string(),
+        }
+    }
+}
+
+pub fn actions(settings: &[Setting], cmd: ConfigSubCommands) -> Result<(), Box<dyn error::Error>> {
+    match cmd {
+        ConfigSubCommands::Show => config_show(settings),
+    }
+}
+
+fn config_show(settings: &[Setting]) -> Result<(), Box<dyn error::Error>> {
+    let key_field_width = settings.iter().map(|s| s.key.len()).max().unwrap_or(10);
+    let value_field_width = settings.iter().map(|s| s.value.len()).max().unwrap_or(10);
+
+    let mut term = console::Term::buffered_stdout();
+
+    for setting in settings.iter() {
+        writeln!(
+            term,
+            "{}    {}    {}",
+            console::pad_str(setting.key, key_field_width, console::Alignment::Left, None),
+            console::pad_str(
+                &setting.value,
+                value_field_width,
+                console::Alignment::Left,
+                None
+            ),
+            console::style(&setting.source).italic()
+        )?;
+    }
+
+    term.flush()?;
+    Ok(())
+}
@@ -1,5 +1,325 @@
+use crate::error::ChopstickError;
+use clap::{Args, ValueEnum};
 use ladle::models::Classifications;
+use serde::Serialize;
 use std::error;
+use std::fs;
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use unidecode::unidecode;
+
+/// Whether progress bars were disabled with `--no-progress`
+static NO_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+pub fn disable_progress() {
+    NO_PROGRESS.store(true, Ordering::Relaxed);
+}
+
+/// Progress bar over `len` steps. The bar is hidden when disabled, when stderr is not a terminal,
+/// and when debug logging is on so that log lines are not mangled by it
+pub fn progress_bar(len: u64, message: &'static str) -> indicatif::ProgressBar {
+    if NO_PROGRESS.load(Ordering::Relaxed)
+        || log::log_enabled!(log::Level::Debug)
+        || !std::io::stderr().is_terminal()
+    {
+        return indicatif::ProgressBar::hidden();
+    }
+
+    indicatif::ProgressBar::new(len)
+        .with_message(message)
+        .with_style(
+            indicatif::ProgressStyle::with_template("{msg:<30} [{wide_bar}] {pos:>4}/{len:4}")
+                .unwrap()
+                .progress_chars("=>-"),
+        )
+}
+
+/// Read clues from a file, one per line. Blank lines are skipped
+pub fn read_clues(path: &Path) -> Result<Vec<String>, Box<dyn error::Error>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Run `action` on every clue. Failures are logged without interrupting the batch, and reported
+/// together at the end. A single clue is handled as if no batch was involved, keeping its error
+pub async fn for_each_clue<'a, F, Fut>(
+    clues: &'a [String],
+    action: F,
+) -> Result<(), Box<dyn error::Error>>
+where
+    F: Fn(&'a str) -> Fut,
+    Fut: Future<Output = Result<(), Box<dyn error::Error>>>,
+{
+    if let [clue] = clues {
+        return action(clue).await;
+    }
+
+    let mut failures = 0;
+    for clue in clues.iter() {
+        if let Err(message) = action(clue).await {
+            failures += 1;
+            log::error!("`{}`: {}", clue, message);
+        }
+    }
+
+    if failures > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "Failed on {} of {} clues",
+            failures,
+            clues.len()
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Number of entities above which commands fetching each of them require `--all`
+static FETCH_THRESHOLD: OnceLock<usize> = OnceLock::new();
+
+const DEFAULT_FETCH_THRESHOLD: usize = 200;
+
+pub fn set_fetch_threshold(threshold: usize) {
+    FETCH_THRESHOLD.get_or_init(|| threshold);
+}
+
+/// Refuse to fetch `count` entities one by one if there are more than the configured threshold,
+/// unless `all` is set. `limitable` tells whether the command offers `--limit`, to suggest it
+pub fn check_fetch_count(
+    count: usize,
+    all: bool,
+    limitable: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let threshold = *FETCH_THRESHOLD.get().unwrap_or(&DEFAULT_FETCH_THRESHOLD);
+
+    if count > threshold && !all {
+        let advice = if limitable {
+            "Narrow the pattern, use --limit or pass --all"
+        } else {
+            "Narrow the pattern or pass --all"
+        };
+        return Err(Box::new(ChopstickError(format!(
+            "This would fetch {} entities, more than the threshold of {}. {}",
+            count, threshold, advice
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Whether clues must match entity names exactly, instead of ignoring case and accents
+static EXACT_NAMES: AtomicBool = AtomicBool::new(false);
+
+pub fn set_exact_names(exact: bool) {
+    EXACT_NAMES.store(exact, Ordering::Relaxed);
+}
+
+/// Find the entry named `clue`. Unless exact names are required, an entry whose name differs only
+/// in case and accents is accepted if no other entry does
+pub fn find_named<'a, T>(entries: &'a [T], name: impl Fn(&T) -> &str, clue: &str) -> Option<&'a T> {
+    if let Some(entry) = entries.iter().find(|e| name(e) == clue) {
+        return Some(entry);
+    }
+
+    if EXACT_NAMES.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let clue = normalize_name(clue);
+    let mut close = entries.iter().filter(|e| normalize_name(name(e)) == clue);
+    match (close.next(), close.next()) {
+        (Some(entry), None) => Some(entry),
+        _ => None,
+    }
+}
+
+/// Output format of listing commands
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns for reading in a terminal
+    #[default]
+    Text,
+
+    /// Comma-separated values with a header line
+    Csv,
+}
+
+/// How created entities are reported on standard output, for scripts to capture
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum CreatedFormat {
+    /// Id of the entity
+    #[default]
+    Id,
+
+    /// Id and name of the entity, separated by a tab
+    Text,
+
+    /// The entity as returned by the server
+    Json,
+}
+
+/// Report a created entity on standard output
+pub fn print_created<T: Serialize>(
+    format: CreatedFormat,
+    id: &str,
+    name: &str,
+    entity: &T,
+) -> Result<(), Box<dyn error::Error>> {
+    match format {
+        CreatedFormat::Id => println!("{}", id),
+        CreatedFormat::Text => println!("{}\t{}", id, name),
+        CreatedFormat::Json => println!("{}", serde_json::to_string_pretty(entity)?),
+    }
+    Ok(())
+}
+
+/// Column layout of text listings
+#[derive(Args, Clone, Debug)]
+pub struct Columns {
+    /// Text written between columns, instead of the listing's default
+    #[arg(long, value_name = "STR")]
+    separator: Option<String>,
+
+    /// Do not pad columns to align them
+    #[arg(long)]
+    no_pad: bool,
+}
+
+impl Columns {
+    /// Write two-column rows to the terminal, padding the first column unless disabled
+    pub fn write_rows(
+        &self,
+        term: &mut console::Term,
+        default_separator: &str,
+        rows: &[(&str, &str)],
+    ) -> Result<(), Box<dyn error::Error>> {
+        let separator = self.separator.as_deref().unwrap_or(default_separator);
+        let width = rows
+            .iter()
+            .map(|(first, _)| unidecode(first).len())
+            .max()
+            .unwrap_or(10);
+
+        for (first, second) in rows.iter() {
+            if self.no_pad {
+                writeln!(term, "{}{}{}", first, separator, second)?;
+            } else {
+                writeln!(
+                    term,
+                    "{}{}{}",
+                    console::pad_str(first, width, console::Alignment::Left, None),
+                    separator,
+                    second
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Dietary classification flag, as given on the command line
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Classification {
+    Dairy,
+    Meat,
+    Gluten,
+    AnimalProduct,
+}
+
+/// Build classifications with the given flags set
+pub fn classifications_from(flags: &[Classification]) -> Classifications {
+    Classifications {
+        dairy: flags.contains(&Classification::Dairy),
+        meat: flags.contains(&Classification::Meat),
+        gluten: flags.contains(&Classification::Gluten),
+        animal_product: flags.contains(&Classification::AnimalProduct),
+    }
+}
+
+/// Client-side filtering of listings on the pattern they were queried with
+#[derive(Args, Clone, Debug)]
+pub struct ExactMatch {
+    /// Only list entries named exactly as the pattern, instead of all the server matches
+    #[arg(long, requires = "pattern")]
+    exact: bool,
+
+    /// Ignore case and accents when comparing names to the pattern
+    #[arg(long, requires = "exact")]
+    fold: bool,
+}
+
+impl ExactMatch {
+    /// Keep the entries named exactly as `pattern`, when exact matching was requested
+    pub fn retain<T>(
+        &self,
+        entries: &mut Vec<T>,
+        name: impl Fn(&T) -> &str,
+        pattern: Option<&str>,
+    ) {
+        let Some(pattern) = pattern.filter(|_| self.exact) else {
+            return;
+        };
+
+        if self.fold {
+            let pattern = normalize_name(pattern);
+            entries.retain(|e| normalize_name(name(e)) == pattern);
+        } else {
+            entries.retain(|e| name(e) == pattern);
+        }
+    }
+}
+
+/// Ascii, lowercase and trimmed form of a name, used to detect near-identical names
+pub fn normalize_name(name: &str) -> String {
+    unidecode(name).trim().to_lowercase()
+}
+
+/// Number of single character insertions, deletions or substitutions to go from one string to
+/// the other
+pub fn edit_distance(lhs: &str, rhs: &str) -> usize {
+    let rhs = rhs.chars().collect::<Vec<_>>();
+    let mut previous = (0..=rhs.len()).collect::<Vec<_>>();
+
+    for (i, l) in lhs.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, r) in rhs.iter().enumerate() {
+            let substitution = previous[j] + usize::from(l != *r);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[rhs.len()]
+}
+
+/// Split text into lines of at most `width` characters, breaking between words. Line breaks in
+/// the text are kept. Words longer than `width` get a line of their own.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+
+    lines
+}
 
 pub fn display_classifications(
     class: &Classifications,
@@ -24,3 +344,34 @@ pub fn display_classifications(
 
     Ok(terms)
 }
+
+/// Current UTC time formatted as `YYYY-MM-DDTHH-MM-SS`, usable in file names and sorting in
+/// chronological order
+pub fn timestamp() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (days, time) = (seconds / 86400, seconds % 86400);
+
+    // Civil date from the number of days since the epoch, in eras of 400 years starting in March
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
@@ -1,10 +1,21 @@
-use crate::error::MatchingError;
-use crate::helpers::display_classifications;
-use clap::Subcommand;
+use crate::error::{ChopstickError, MatchingError};
+use crate::helpers::{
+    check_fetch_count, classifications_from, display_classifications, edit_distance, find_named,
+    normalize_name, print_created, progress_bar, Classification, Columns, CreatedFormat,
+    ExactMatch, OutputFormat,
+};
+use crate::maintenance_actions::fetch_ingredients;
+use clap::{Subcommand, ValueEnum};
 use futures::future::join_all;
-use ladle::models::{Ingredient, IngredientIndex};
+use futures::stream::{self, StreamExt};
+use ladle::models::{classifications_union, Classifications, Ingredient, IngredientIndex};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use std::error;
+use std::fs;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use unidecode::unidecode;
 
 /// Ingredient fetching and edition family of commands
@@ -14,12 +25,40 @@ pub enum IngredientSubCommands {
     List {
         /// Ingredient name pattern to match in list
         pattern: Option<String>,
+
+        /// Only list the first N matching ingredients
+        #[arg(short, long, value_name = "N")]
+        limit: Option<usize>,
+
+        /// Fetch details of all matching ingredients, even above the fetch threshold
+        #[arg(long)]
+        all: bool,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        exact: ExactMatch,
+
+        #[command(flatten)]
+        columns: Columns,
     },
 
     /// Fetch details about an ingredient
     Show {
         /// Ingredient name, id or identifying pattern
         clue: String,
+
+        /// Print the server's response as is
+        #[arg(long)]
+        raw: bool,
+    },
+
+    /// Show the recipes that would lose an ingredient if it was deleted
+    Impact {
+        /// Ingredient name, id or identifying pattern
+        clue: String,
     },
 
     /// Create an ingredient
@@ -42,6 +81,17 @@ pub enum IngredientSubCommands {
         /// Mark the ingredient as containing animal products
         #[arg(short, long, default_value_t = false)]
         animal_product: bool,
+
+        /// How to report the created ingredient on standard output
+        #[arg(long, value_enum, default_value_t = CreatedFormat::Id)]
+        format: CreatedFormat,
+    },
+
+    /// Create ingredients from a file with one ingredient per line. Classifications can follow
+    /// the name after a colon, as in `Milk:dairy,animal_product`
+    Import {
+        /// Path to the ingredient list
+        file: PathBuf,
     },
 
     /// Edit an ingredient
@@ -70,38 +120,152 @@ pub enum IngredientSubCommands {
         animal_product: Option<bool>,
     },
 
+    /// Rename an ingredient, refusing to create a duplicate of an existing ingredient
+    Rename {
+        /// Ingredient name, id or identifying pattern
+        clue: String,
+
+        /// New name of the ingredient
+        name: String,
+    },
+
+    /// Change the classifications of all ingredients matching a pattern
+    #[command(group(
+        clap::ArgGroup::new("changes")
+            .required(true)
+            .multiple(true)
+            .args(["dairy", "meat", "gluten", "animal_product"])
+    ))]
+    SetClass {
+        /// Ingredient name pattern selecting the ingredients to edit
+        #[arg(long, value_name = "PATTERN")]
+        matching: String,
+
+        /// Change the ingredients' dairy content
+        #[arg(short, long)]
+        dairy: Option<bool>,
+
+        /// Change the ingredients' meat content
+        #[arg(short, long)]
+        meat: Option<bool>,
+
+        /// Change the ingredients' gluten content
+        #[arg(short, long)]
+        gluten: Option<bool>,
+
+        /// Change the ingredients' animal product content
+        #[arg(short, long)]
+        animal_product: Option<bool>,
+
+        /// List the matching ingredients without editing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// Delete an ingredient
     Delete {
         /// Ingredient id matching the ingredient to delete
         id: String,
     },
 
-    /// Merge one ingredient into another and update all recipes dependent on the former
+    /// Report unused, unclassified and likely duplicate ingredients
+    Audit,
+
+    /// Explain the classification flags and how they are displayed
+    Classes,
+
+    /// Compare the classifications of the server's ingredients with a reference CSV file, with
+    /// columns `name`, `dairy`, `meat`, `gluten` and `animal_product`
+    AuditClass {
+        /// Reference CSV file
+        reference: PathBuf,
+
+        /// Apply the reference classifications to the mismatching ingredients
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Merge one ingredient into another and update all recipes dependent on the former. The kept
+    /// ingredient takes on the classifications of both
     Merge {
         /// Ingredient to keep
         unified_clue: String,
 
         /// Ingredient to merge and delete
         obsolete_clue: String,
+
+        /// Migrate the requirements but do not delete the merged ingredient
+        #[arg(long)]
+        keep_source: bool,
+    },
+
+    /// Clean up the names of all ingredients. Without `--trim` nor `--title-case`, both are
+    /// applied
+    NormalizeNames {
+        /// Remove leading, trailing and repeated whitespace
+        #[arg(long)]
+        trim: bool,
+
+        /// Capitalize the first letter of each word and lowercase the others
+        #[arg(long)]
+        title_case: bool,
+
+        /// What to do when a normalized name is already taken by another ingredient
+        #[arg(long, value_enum, default_value_t = OnConflict::Skip)]
+        on_conflict: OnConflict,
+
+        /// List the proposed names without renaming anything
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
+/// Resolution of a normalized name already taken by another ingredient
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum OnConflict {
+    /// Leave the ingredient as is
+    Skip,
+    /// Merge the ingredient into the one holding the name
+    Merge,
+    /// Ask whether to merge each ingredient
+    Prompt,
+}
+
 pub async fn actions(
     origin: &str,
     cmd: IngredientSubCommands,
 ) -> Result<(), Box<dyn error::Error>> {
     match cmd {
-        IngredientSubCommands::List { pattern } => {
-            ingredient_list(origin, pattern.as_deref()).await
+        IngredientSubCommands::List {
+            pattern,
+            limit,
+            all,
+            format,
+            exact,
+            columns,
+        } => {
+            ingredient_list(
+                origin,
+                pattern.as_deref(),
+                limit,
+                all,
+                format,
+                &exact,
+                &columns,
+            )
+            .await
         }
-        IngredientSubCommands::Show { clue } => ingredient_show(origin, &clue).await,
+        IngredientSubCommands::Show { clue, raw } => ingredient_show(origin, &clue, raw).await,
+        IngredientSubCommands::Impact { clue } => ingredient_impact(origin, &clue).await,
         IngredientSubCommands::Create {
             name,
             dairy,
             meat,
             gluten,
             animal_product,
-        } => ingredient_create(origin, &name, dairy, meat, gluten, animal_product).await,
+            format,
+        } => ingredient_create(origin, &name, dairy, meat, gluten, animal_product, format).await,
+        IngredientSubCommands::Import { file } => ingredient_import(origin, &file).await,
         IngredientSubCommands::Edit {
             clue,
             name,
@@ -121,51 +285,141 @@ pub async fn actions(
             )
             .await
         }
+        IngredientSubCommands::Rename { clue, name } => {
+            ingredient_rename(origin, &clue, &name).await
+        }
+        IngredientSubCommands::SetClass {
+            matching,
+            dairy,
+            meat,
+            gluten,
+            animal_product,
+            dry_run,
+        } => {
+            ingredient_set_class(
+                origin,
+                &matching,
+                dairy,
+                meat,
+                gluten,
+                animal_product,
+                dry_run,
+            )
+            .await
+        }
         IngredientSubCommands::Delete { id } => ingredient_delete(origin, &id).await,
+        IngredientSubCommands::Audit => ingredient_audit(origin).await,
+        IngredientSubCommands::Classes => ingredient_classes(),
+        IngredientSubCommands::AuditClass { reference, fix } => {
+            ingredient_audit_class(origin, &reference, fix).await
+        }
         IngredientSubCommands::Merge {
             unified_clue,
             obsolete_clue,
-        } => ingredient_merge(origin, &unified_clue, &obsolete_clue).await,
+            keep_source,
+        } => ingredient_merge(origin, &unified_clue, &obsolete_clue, keep_source).await,
+        IngredientSubCommands::NormalizeNames {
+            trim,
+            title_case,
+            on_conflict,
+            dry_run,
+        } => {
+            // Apply every normalization when none is picked
+            let (trim, title_case) = match (trim, title_case) {
+                (false, false) => (true, true),
+                picked => picked,
+            };
+            ingredient_normalize_names(origin, trim, title_case, on_conflict, dry_run).await
+        }
     }
 }
 
-async fn ingredient_list(origin: &str, pattern: Option<&str>) -> Result<(), Box<dyn error::Error>> {
+async fn ingredient_list(
+    origin: &str,
+    pattern: Option<&str>,
+    limit: Option<usize>,
+    all: bool,
+    format: OutputFormat,
+    exact: &ExactMatch,
+    columns: &Columns,
+) -> Result<(), Box<dyn error::Error>> {
     let mut ingredients = ladle::ingredient_index(origin, pattern.unwrap_or("")).await?;
+    exact.retain(&mut ingredients, |i| &i.name, pattern);
     ingredients.sort_by(|lhs, rhs| unidecode(&lhs.name).cmp(&unidecode(&rhs.name)));
 
-    let name_field_width = ingredients
+    if let Some(limit) = limit {
+        ingredients.truncate(limit);
+    }
+
+    if let OutputFormat::Csv = format {
+        check_fetch_count(ingredients.len(), all, true)?;
+        return ingredient_list_csv(origin, &ingredients).await;
+    }
+
+    let rows = ingredients
         .iter()
-        .map(|r| unidecode(&r.name).len())
-        .max()
-        .unwrap_or(10);
+        .map(|i| (i.name.as_str(), i.id.as_str()))
+        .collect::<Vec<_>>();
     let mut term = console::Term::buffered_stdout();
+    columns.write_rows(&mut term, "    ", &rows)?;
+    term.flush()?;
+    Ok(())
+}
 
-    for index in ingredients.iter() {
-        write!(
-            term,
-            "{}    {}\n",
-            console::pad_str(
-                &index.name,
-                name_field_width,
-                console::Alignment::Left,
-                None
-            ),
-            index.id
-        )?;
+/// Output ingredients as CSV. Classifications are not part of the index, so every ingredient is
+/// fetched
+async fn ingredient_list_csv(
+    origin: &str,
+    ingredients: &[IngredientIndex],
+) -> Result<(), Box<dyn error::Error>> {
+    let fetches = ingredients
+        .iter()
+        .map(|ingredient| ladle::ingredient_get(origin, &ingredient.id));
+
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+    writer.write_record(["id", "name", "dairy", "meat", "gluten", "animal_product"])?;
+
+    let mut responses = stream::iter(fetches).buffered(ladle::bulk::CONCURRENCY);
+    while let Some(ingredient) = responses.next().await {
+        let Ingredient {
+            id,
+            name,
+            classifications,
+            used_in: _,
+            updated_at: _,
+        } = ingredient?;
+
+        writer.write_record([
+            id,
+            name,
+            classifications.dairy.to_string(),
+            classifications.meat.to_string(),
+            classifications.gluten.to_string(),
+            classifications.animal_product.to_string(),
+        ])?;
     }
 
-    term.flush()?;
+    writer.flush()?;
     Ok(())
 }
 
-async fn ingredient_show(origin: &str, id: &str) -> Result<(), Box<dyn error::Error>> {
-    let ingredient = ingredient_identify(origin, id, false).await?;
+async fn ingredient_show(origin: &str, id: &str, raw: bool) -> Result<(), Box<dyn error::Error>> {
+    let ingredient = ingredient_identify(origin, id, None).await?;
+
+    if raw {
+        println!(
+            "{}",
+            ladle::ingredient_get_raw(origin, &ingredient.id).await?
+        );
+        return Ok(());
+    }
 
     let Ingredient {
         id: _,
         name,
         classifications,
         used_in,
+        updated_at: _,
     } = ladle::ingredient_get(origin, &ingredient.id).await?;
 
     let mut term = console::Term::buffered_stdout();
@@ -200,8 +454,99 @@ async fn ingredient_create(
     meat: bool,
     gluten: bool,
     animal_product: bool,
+    format: CreatedFormat,
 ) -> Result<(), Box<dyn error::Error>> {
-    ladle::ingredient_create(origin, name, dairy, meat, gluten, animal_product).await?;
+    let creation =
+        ladle::ingredient_create_checked(origin, name, dairy, meat, gluten, animal_product).await?;
+    if !creation.created {
+        log::warn!(
+            "Ingredient `{}` already exists ({})",
+            creation.object.name,
+            creation.object.id
+        );
+    }
+    print_created(
+        format,
+        &creation.object.id,
+        &creation.object.name,
+        &creation.object,
+    )
+}
+
+/// Parse an ingredient list line, made of a name optionally followed by a colon and
+/// comma-separated classifications
+fn parse_import_line(line: &str) -> Result<(&str, Classifications), String> {
+    let (name, markers) = line.split_once(':').unwrap_or((line, ""));
+
+    let flags = markers
+        .split(',')
+        .map(str::trim)
+        .filter(|marker| !marker.is_empty())
+        .map(|marker| {
+            Classification::from_str(&marker.replace('_', "-"), true)
+                .map_err(|_| format!("Unknown classification `{}`", marker))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((name.trim(), classifications_from(&flags)))
+}
+
+async fn ingredient_import(origin: &str, file: &Path) -> Result<(), Box<dyn error::Error>> {
+    let contents = fs::read_to_string(file)?;
+
+    let mut entries = vec![];
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry = parse_import_line(line).map_err(|message| {
+            ChopstickError(format!("{}:{}: {}", file.display(), number + 1, message))
+        })?;
+        entries.push(entry);
+    }
+
+    let bar = progress_bar(entries.len() as u64, "Creating ingredients");
+
+    let (mut created, mut skipped, mut failures) = (0, vec![], 0);
+    for (name, classifications) in entries.iter() {
+        bar.inc(1);
+        match ladle::ingredient_create_checked(
+            origin,
+            name,
+            classifications.dairy,
+            classifications.meat,
+            classifications.gluten,
+            classifications.animal_product,
+        )
+        .await
+        {
+            Ok(creation) if creation.created => created += 1,
+            Ok(creation) => skipped.push(creation.object),
+            Err(message) => {
+                failures += 1;
+                log::error!("Failed to create ingredient `{}`: {}", name, message);
+            }
+        }
+    }
+
+    bar.finish_and_clear();
+
+    let mut term = console::Term::buffered_stdout();
+    writeln!(term, "Ingredients created:    {}", created)?;
+    writeln!(term, "Ingredients skipped:    {}", skipped.len())?;
+    for ingredient in skipped.iter() {
+        writeln!(term, "  - {} ({})", ingredient.name, ingredient.id)?;
+    }
+    term.flush()?;
+
+    if failures > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "Failed to create {} ingredients",
+            failures
+        ))));
+    }
+
     Ok(())
 }
 
@@ -214,7 +559,7 @@ async fn ingredient_edit(
     gluten: Option<bool>,
     animal_product: Option<bool>,
 ) -> Result<(), Box<dyn error::Error>> {
-    let ingredient = ingredient_identify(origin, id, false).await?;
+    let ingredient = ingredient_identify(origin, id, None).await?;
 
     ladle::ingredient_update(
         origin,
@@ -228,25 +573,581 @@ async fn ingredient_edit(
     .await
 }
 
+async fn ingredient_rename(
+    origin: &str,
+    clue: &str,
+    name: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    let ingredient = ingredient_identify(origin, clue, None).await?;
+
+    let collision = ladle::ingredient_index(origin, name)
+        .await?
+        .into_iter()
+        .find(|i| i.id != ingredient.id && normalize_name(&i.name) == normalize_name(name));
+    if let Some(existing) = collision {
+        return Err(Box::new(ChopstickError(format!(
+            "Ingredient `{}` ({}) already exists. Merge them instead with `chopstick ingredient merge {} {}`",
+            existing.name, existing.id, existing.id, ingredient.id
+        ))));
+    }
+
+    ladle::ingredient_update(origin, &ingredient.id, Some(name), None, None, None, None).await?;
+    log::info!("Renamed ingredient `{}` to `{}`", ingredient.name, name);
+    Ok(())
+}
+
+async fn ingredient_set_class(
+    origin: &str,
+    pattern: &str,
+    dairy: Option<bool>,
+    meat: Option<bool>,
+    gluten: Option<bool>,
+    animal_product: Option<bool>,
+    dry_run: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let matches = ladle::ingredient_index(origin, pattern).await?;
+
+    if matches.is_empty() {
+        log::info!("No ingredients match `{}`", pattern);
+        return Ok(());
+    }
+
+    if dry_run {
+        for ingredient in matches.iter() {
+            println!("{}\t{}", ingredient.id, ingredient.name);
+        }
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    for ingredient in matches.iter() {
+        match ladle::ingredient_update(
+            origin,
+            &ingredient.id,
+            None,
+            dairy,
+            meat,
+            gluten,
+            animal_product,
+        )
+        .await
+        {
+            Ok(()) => log::debug!(
+                "Updated ingredient `{}` ({})",
+                ingredient.name,
+                ingredient.id
+            ),
+            Err(message) => {
+                failures += 1;
+                log::error!(
+                    "Failed to update ingredient `{}`: {}",
+                    ingredient.name,
+                    message
+                )
+            }
+        }
+    }
+
+    log::info!("Updated {} ingredients", matches.len() - failures);
+
+    if failures > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "Failed to update {} ingredients",
+            failures
+        ))));
+    }
+
+    Ok(())
+}
+
 async fn ingredient_delete(origin: &str, id: &str) -> Result<(), Box<dyn error::Error>> {
-    let ingredient = ingredient_identify(origin, id, false).await?;
+    let ingredient = ingredient_identify(origin, id, None).await?;
 
     ladle::ingredient_delete(origin, &ingredient.id).await
 }
 
+/// Report the recipes requiring an ingredient, telling apart those requiring it optionally
+async fn ingredient_impact(origin: &str, clue: &str) -> Result<(), Box<dyn error::Error>> {
+    let index = ingredient_identify(origin, clue, None).await?;
+    let ingredient = ladle::ingredient_get(origin, &index.id).await?;
+
+    let (mut mandatory, mut optional) = (vec![], vec![]);
+    for recipe in ingredient.used_in.iter() {
+        let requirements = ladle::recipe_get_requirements(origin, &recipe.id).await?;
+        match requirements
+            .iter()
+            .find(|r| r.ingredient.id == ingredient.id)
+        {
+            Some(requirement) if requirement.optional => optional.push(recipe),
+            _ => mandatory.push(recipe),
+        }
+    }
+
+    let mut term = console::Term::buffered_stdout();
+
+    if mandatory.is_empty() && optional.is_empty() {
+        writeln!(term, "`{}` is not used by any recipe", ingredient.name)?;
+    }
+
+    for (heading, recipes) in [
+        ("Recipes requiring it", &mandatory),
+        ("Recipes listing it as optional", &optional),
+    ] {
+        if recipes.is_empty() {
+            continue;
+        }
+        writeln!(term, "{}", console::style(heading).bold())?;
+        for recipe in recipes.iter() {
+            writeln!(term, "  - {} ({})", recipe.name, recipe.id)?;
+        }
+    }
+
+    term.flush()?;
+    Ok(())
+}
+
+/// Read-only report of the ingredients worth a look before merging or cleaning
+async fn ingredient_audit(origin: &str) -> Result<(), Box<dyn error::Error>> {
+    let mut ingredients = fetch_ingredients(origin)
+        .await?
+        .into_iter()
+        .collect::<Vec<_>>();
+    ingredients.sort_by_key(|i| normalize_name(&i.name));
+
+    let mut term = console::Term::buffered_stdout();
+
+    writeln!(term, "{}", console::style("Unused ingredients").bold())?;
+    for ingredient in ingredients.iter().filter(|i| i.used_in.is_empty()) {
+        writeln!(term, "  - {} ({})", ingredient.name, ingredient.id)?;
+    }
+
+    writeln!(
+        term,
+        "\n{}",
+        console::style("Unclassified ingredients").bold()
+    )?;
+    for ingredient in ingredients
+        .iter()
+        .filter(|i| i.classifications == Classifications::default())
+    {
+        writeln!(term, "  - {} ({})", ingredient.name, ingredient.id)?;
+    }
+
+    // Group ingredients whose normalized names are at most one edit apart
+    let names = ingredients
+        .iter()
+        .map(|i| normalize_name(&i.name))
+        .collect::<Vec<_>>();
+    let mut grouped = vec![false; ingredients.len()];
+
+    writeln!(term, "\n{}", console::style("Likely duplicates").bold())?;
+    for i in 0..ingredients.len() {
+        if grouped[i] {
+            continue;
+        }
+
+        let group = (i + 1..ingredients.len())
+            .filter(|j| !grouped[*j] && edit_distance(&names[i], &names[*j]) <= 1)
+            .collect::<Vec<_>>();
+
+        if group.is_empty() {
+            continue;
+        }
+
+        let members = std::iter::once(i)
+            .chain(group.iter().cloned())
+            .map(|k| {
+                grouped[k] = true;
+                format!("{} ({})", ingredients[k].name, ingredients[k].id)
+            })
+            .collect::<Vec<_>>();
+        writeln!(term, "  - {}", members.join(", "))?;
+    }
+
+    term.flush()?;
+    Ok(())
+}
+
+/// Print the classification legend. Displayed terms come from `display_classifications`, so that
+/// the legend follows its rules
+fn ingredient_classes() -> Result<(), Box<dyn error::Error>> {
+    let flags = [
+        (
+            "dairy",
+            "Contains milk or milk products",
+            Classifications {
+                dairy: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "meat",
+            "Contains meat",
+            Classifications {
+                meat: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "gluten",
+            "Contains gluten",
+            Classifications {
+                gluten: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "animal_product",
+            "Contains products of animal origin, as eggs or honey",
+            Classifications {
+                animal_product: true,
+                ..Default::default()
+            },
+        ),
+    ];
+
+    let mut term = console::Term::buffered_stdout();
+
+    writeln!(term, "{}", console::style("Flags").bold())?;
+    for (flag, meaning, class) in flags.iter() {
+        writeln!(
+            term,
+            "  {:<16}{}, displayed as `{}`",
+            flag,
+            meaning,
+            display_classifications(class)?.join(", ")
+        )?;
+    }
+
+    let meat_and_animal = Classifications {
+        meat: true,
+        animal_product: true,
+        ..Default::default()
+    };
+    writeln!(
+        term,
+        "\n  Animal products are not displayed alongside meat: both flags are displayed as `{}`",
+        display_classifications(&meat_and_animal)?.join(", ")
+    )?;
+
+    term.flush()?;
+    Ok(())
+}
+
+/// Line of a reference classification file
+#[derive(Deserialize)]
+struct ReferenceClassification {
+    name: String,
+    dairy: bool,
+    meat: bool,
+    gluten: bool,
+    animal_product: bool,
+}
+
+/// Load a reference classification file, indexed by normalized ingredient name
+fn load_reference(
+    path: &Path,
+) -> Result<HashMap<String, (String, Classifications)>, Box<dyn error::Error>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let mut reference = HashMap::new();
+
+    for record in reader.deserialize() {
+        let ReferenceClassification {
+            name,
+            dairy,
+            meat,
+            gluten,
+            animal_product,
+        } = record?;
+
+        let classifications = Classifications {
+            dairy,
+            meat,
+            gluten,
+            animal_product,
+        };
+        reference.insert(normalize_name(&name), (name, classifications));
+    }
+
+    Ok(reference)
+}
+
+/// Report the ingredients whose classifications differ from the reference, and the entries
+/// missing on either side. With `fix`, the reference classifications are applied.
+async fn ingredient_audit_class(
+    origin: &str,
+    reference_path: &Path,
+    fix: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let reference = load_reference(reference_path)?;
+
+    let mut ingredients = fetch_ingredients(origin)
+        .await?
+        .into_iter()
+        .collect::<Vec<_>>();
+    ingredients.sort_by_key(|i| normalize_name(&i.name));
+
+    let mut mismatches = vec![];
+    let mut unreferenced = vec![];
+    for ingredient in ingredients.iter() {
+        match reference.get(&normalize_name(&ingredient.name)) {
+            Some((_, expected)) if *expected != ingredient.classifications => {
+                mismatches.push((ingredient, expected))
+            }
+            Some(_) => (),
+            None => unreferenced.push(ingredient),
+        }
+    }
+
+    let on_server = ingredients
+        .iter()
+        .map(|i| normalize_name(&i.name))
+        .collect::<HashSet<_>>();
+    let mut missing = reference
+        .iter()
+        .filter(|(key, _)| !on_server.contains(*key))
+        .map(|(_, (name, _))| name.as_str())
+        .collect::<Vec<_>>();
+    missing.sort_by_key(|name| normalize_name(name));
+
+    let mut term = console::Term::buffered_stdout();
+
+    writeln!(term, "{}", console::style("Mismatching ingredients").bold())?;
+    for (ingredient, expected) in mismatches.iter() {
+        writeln!(
+            term,
+            "  - {} ({}): [{}] instead of [{}]",
+            ingredient.name,
+            ingredient.id,
+            display_classifications(&ingredient.classifications)?.join(", "),
+            display_classifications(expected)?.join(", ")
+        )?;
+    }
+
+    writeln!(
+        term,
+        "\n{}",
+        console::style("Ingredients missing from the server").bold()
+    )?;
+    for name in missing.iter() {
+        writeln!(term, "  - {}", name)?;
+    }
+
+    writeln!(
+        term,
+        "\n{}",
+        console::style("Ingredients missing from the reference").bold()
+    )?;
+    for ingredient in unreferenced.iter() {
+        writeln!(term, "  - {} ({})", ingredient.name, ingredient.id)?;
+    }
+
+    term.flush()?;
+
+    if !fix {
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    for (ingredient, expected) in mismatches.iter() {
+        match ladle::ingredient_update(
+            origin,
+            &ingredient.id,
+            None,
+            Some(expected.dairy),
+            Some(expected.meat),
+            Some(expected.gluten),
+            Some(expected.animal_product),
+        )
+        .await
+        {
+            Ok(()) => log::debug!(
+                "Updated ingredient `{}` ({})",
+                ingredient.name,
+                ingredient.id
+            ),
+            Err(message) => {
+                failures += 1;
+                log::error!(
+                    "Failed to update ingredient `{}`: {}",
+                    ingredient.name,
+                    message
+                )
+            }
+        }
+    }
+
+    log::info!("Updated {} ingredients", mismatches.len() - failures);
+
+    if failures > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "Failed to update {} ingredients",
+            failures
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Normalized form of an ingredient name
+fn normalized_ingredient_name(name: &str, trim: bool, title_case: bool) -> String {
+    let name = if trim {
+        name.split_whitespace().collect::<Vec<_>>().join(" ")
+    } else {
+        name.to_string()
+    };
+
+    if !title_case {
+        return name;
+    }
+
+    let mut titled = String::with_capacity(name.len());
+    let mut word_start = true;
+    for c in name.chars() {
+        if word_start {
+            titled.extend(c.to_uppercase());
+        } else {
+            titled.extend(c.to_lowercase());
+        }
+        word_start = c.is_whitespace();
+    }
+    titled
+}
+
+async fn ingredient_normalize_names(
+    origin: &str,
+    trim: bool,
+    title_case: bool,
+    on_conflict: OnConflict,
+    dry_run: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut ingredients = fetch_ingredients(origin)
+        .await?
+        .into_iter()
+        .collect::<Vec<_>>();
+    ingredients.sort_by_key(|i| unidecode(&i.name));
+
+    let renames = ingredients
+        .iter()
+        .map(|i| (i, normalized_ingredient_name(&i.name, trim, title_case)))
+        .filter(|(i, name)| &i.name != name)
+        .collect::<Vec<_>>();
+
+    if renames.is_empty() {
+        log::info!("All ingredient names are already normalized");
+        return Ok(());
+    }
+
+    // Names held once the renames are done, with the ingredient holding them. Renamed ingredients
+    // release their former name, and claim their new one unless it is taken
+    let renamed = renames
+        .iter()
+        .map(|(i, _)| i.id.as_str())
+        .collect::<HashSet<_>>();
+    let mut holders = ingredients
+        .iter()
+        .filter(|i| !renamed.contains(i.id.as_str()))
+        .map(|i| (i.name.clone(), i))
+        .collect::<HashMap<_, _>>();
+    let mut conflicts = vec![];
+    let mut free = vec![];
+    for (ingredient, name) in renames.iter() {
+        match holders.get(name) {
+            Some(holder) => conflicts.push((*ingredient, name, *holder)),
+            None => {
+                holders.insert(name.clone(), ingredient);
+                free.push((*ingredient, name));
+            }
+        }
+    }
+
+    if dry_run {
+        for (ingredient, name) in free.iter() {
+            println!("{}\t{} -> {}", ingredient.id, ingredient.name, name);
+        }
+        for (ingredient, name, holder) in conflicts.iter() {
+            println!(
+                "{}\t{} -> {} (taken by {})",
+                ingredient.id, ingredient.name, name, holder.id
+            );
+        }
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    for (ingredient, name) in free.iter() {
+        match ladle::ingredient_update(origin, &ingredient.id, Some(name), None, None, None, None)
+            .await
+        {
+            Ok(()) => log::info!("Renamed `{}` to `{}`", ingredient.name, name),
+            Err(message) => {
+                failures += 1;
+                log::error!("Failed to rename `{}`: {}", ingredient.name, message)
+            }
+        }
+    }
+
+    for (ingredient, name, holder) in conflicts.iter() {
+        let merge = match on_conflict {
+            OnConflict::Skip => false,
+            OnConflict::Merge => true,
+            OnConflict::Prompt => dialoguer::Confirm::new()
+                .with_prompt(format!(
+                    "`{}` would be renamed `{}`, already taken. Merge it into `{}` ?",
+                    ingredient.name, name, holder.id
+                ))
+                .default(false)
+                .interact()?,
+        };
+
+        if !merge {
+            log::warn!(
+                "Skipped renaming `{}` to `{}`: name taken by {}",
+                ingredient.name,
+                name,
+                holder.id
+            );
+            continue;
+        }
+
+        if let Err(message) = ingredient_merge(origin, &holder.id, &ingredient.id, false).await {
+            failures += 1;
+            log::error!(
+                "Failed to merge `{}` into `{}`: {}",
+                ingredient.name,
+                name,
+                message
+            )
+        }
+    }
+
+    if failures > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "Failed to normalize {} ingredient names",
+            failures
+        ))));
+    }
+
+    Ok(())
+}
+
 /// Given two ingredient ids, migrate all requirements involving the obsolete id to the main id,
-/// then delete the obsolete ingredient
+/// then delete the obsolete ingredient unless `keep_source` is set. The main ingredient takes on
+/// the classifications of both
 async fn ingredient_merge(
     origin: &str,
     target_clue: &str,
     obsolete_clue: &str,
+    keep_source: bool,
 ) -> Result<(), Box<dyn error::Error>> {
-    let target_id = ingredient_identify(origin, target_clue, false).await?.id;
-    let obsolete_id = ingredient_identify(origin, obsolete_clue, false).await?.id;
+    let target_id = ingredient_identify(origin, target_clue, None).await?.id;
+    let obsolete = ingredient_identify(origin, obsolete_clue, None).await?;
+    let obsolete_id = obsolete.id.clone();
 
-    let uses = ladle::ingredient_get(origin, &obsolete_id).await?;
+    let target = ladle::ingredient_get(origin, &target_id).await?;
+    let source = ladle::ingredient_get(origin, &obsolete_id).await?;
 
-    let uses = uses.used_in.iter().map(|recipe| async {
+    let uses = source.used_in.iter().map(|recipe| async {
         match ladle::recipe_get_requirements(origin, &recipe.id)
             .await
             .unwrap_or(vec![])
@@ -268,30 +1169,134 @@ async fn ingredient_merge(
         .collect::<Vec<(String, String)>>();
 
     let additions = targets.iter().map(|(recipe_id, quantity)| async {
-        ladle::requirement_create(origin, recipe_id, &target_id, quantity, false).await
+        let requirement = ladle::NewRequirement {
+            recipe_id,
+            ingredient_id: &target_id,
+            quantity,
+            optional: false,
+        };
+        ladle::requirement_add(origin, requirement).await
     });
 
-    let deletions = targets.iter().map(|(recipe_id, _)| async {
+    // Only delete the requirements that were replaced, so that no recipe loses the ingredient
+    let mut failures = 0;
+    let mut added = vec![];
+    for ((recipe_id, _), addition) in targets.iter().zip(join_all(additions).await) {
+        match addition {
+            Ok(()) => added.push(recipe_id),
+            Err(message) => {
+                failures += 1;
+                log::error!(
+                    "Failed to migrate requirement of recipe {}: {}",
+                    recipe_id,
+                    message
+                );
+            }
+        }
+    }
+
+    let deletions = added.iter().map(|recipe_id| async {
         ladle::requirement_delete(origin, recipe_id, &obsolete_id).await
     });
 
-    join_all(additions).await;
-    join_all(deletions).await;
+    let mut migrated = 0;
+    for (recipe_id, deletion) in added.iter().zip(join_all(deletions).await) {
+        match deletion {
+            Ok(()) => migrated += 1,
+            Err(message) => {
+                failures += 1;
+                log::error!(
+                    "Failed to remove obsolete requirement of recipe {}: {}",
+                    recipe_id,
+                    message
+                );
+            }
+        }
+    }
+
+    // Deleting the obsolete ingredient would break the recipes still requiring it
+    if failures > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "Migrated {} of {} requirements, {} failures. Kept ingredient `{}`",
+            migrated,
+            targets.len(),
+            failures,
+            obsolete.name
+        ))));
+    }
+
+    // The recipes migrated to the kept ingredient must not lose the flags of the merged one
+    let classifications = classifications_union([&target.classifications, &source.classifications]);
+    if classifications != target.classifications {
+        ladle::ingredient_update(
+            origin,
+            &target_id,
+            None,
+            Some(classifications.dairy),
+            Some(classifications.meat),
+            Some(classifications.gluten),
+            Some(classifications.animal_product),
+        )
+        .await?;
+        log::info!(
+            "Set the classifications of `{}` to {}",
+            target.name,
+            classifications
+        );
+    }
+
+    if keep_source {
+        log::info!(
+            "Migrated {} requirements, kept ingredient `{}`",
+            migrated,
+            obsolete.name
+        );
+        return Ok(());
+    }
+
     ladle::ingredient_delete(origin, &obsolete_id).await?;
 
+    log::info!(
+        "Migrated {} requirements, deleted ingredient `{}`",
+        migrated,
+        obsolete.name
+    );
+
+    Ok(())
+}
+
+/// Alias table mapping alternative ingredient names to their canonical name
+static ALIASES: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Load the alias table from a TOML file consulted by `ingredient_identify`
+pub fn load_aliases(path: &Path) -> Result<(), Box<dyn error::Error>> {
+    let aliases: HashMap<String, String> = toml::from_str(&fs::read_to_string(path)?)?;
+    log::debug!("Loaded {} aliases from {}", aliases.len(), path.display());
+    ALIASES.get_or_init(|| aliases);
     Ok(())
 }
 
+/// Resolve an ingredient from its id, name or an identifying pattern. If `create` is given and no
+/// ingredient matches, one is created with these classifications.
 pub async fn ingredient_identify(
     url: &str,
     clue: &str,
-    create: bool,
+    create: Option<&Classifications>,
 ) -> Result<IngredientIndex, Box<dyn error::Error>> {
+    let clue = match ALIASES.get().and_then(|aliases| aliases.get(clue)) {
+        Some(canonical) => {
+            log::info!("Resolved ingredient alias `{}` to `{}`", clue, canonical);
+            canonical.as_str()
+        }
+        None => clue,
+    };
+
     if let Ok(Ingredient {
         name,
         id,
         classifications: _,
         used_in: _,
+        updated_at: _,
     }) = ladle::ingredient_get(url, clue).await
     {
         return Ok(IngredientIndex { id, name });
@@ -311,14 +1316,25 @@ pub async fn ingredient_identify(
         return Ok(ingredient.to_owned());
     }
 
-    for indice in matches.iter() {
-        if indice.name == clue {
-            return Ok(indice.to_owned());
+    if let Some(indice) = find_named(&matches, |i| &i.name, clue) {
+        if indice.name != clue {
+            log::info!("Identified ingredient `{}` from `{}`", indice.name, clue);
         }
+        return Ok(indice.to_owned());
     }
 
-    if create {
-        ladle::ingredient_create(url, clue, false, false, false, false).await
+    if let Some(classifications) = create {
+        let created = ladle::ingredient_create(
+            url,
+            clue,
+            classifications.dairy,
+            classifications.meat,
+            classifications.gluten,
+            classifications.animal_product,
+        )
+        .await?;
+        log::info!("Created ingredient `{}` ({})", created.name, created.id);
+        Ok(created)
     } else {
         Err(Box::new(MatchingError(
             format!("Failed to identify ingredient from: `{}`", clue),
@@ -1,25 +1,48 @@
-use crate::error::ChopstickError;
-use clap::Subcommand;
-use ladle::models::{Label, LabelIndex};
+use crate::error::{ChopstickError, MatchingError, NotFoundError};
+use crate::helpers::{find_named, print_created, Columns, CreatedFormat, ExactMatch, OutputFormat};
+use clap::{Subcommand, ValueEnum};
+use ladle::models::{Label, LabelIndex, RecipeIndex};
+use serde::Serialize;
 use std::error;
+use std::io::Write;
+use unidecode::unidecode;
 
 /// Label fetching and edition family of commands
 #[derive(Subcommand)]
 pub enum LabelSubCommands {
+    /// List labels from the server
     List {
         /// Label name pattern to match in list
         pattern: Option<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        exact: ExactMatch,
+
+        #[command(flatten)]
+        columns: Columns,
     },
 
     Show {
         /// Label name, id or identifying pattern
         clue: String,
+
+        /// Print the server's response as is
+        #[arg(long)]
+        raw: bool,
     },
 
     /// Create a label
     Create {
         /// Label name
         name: String,
+
+        /// How to report the created label on standard output
+        #[arg(long, value_enum, default_value_t = CreatedFormat::Id)]
+        format: CreatedFormat,
     },
 
     /// Edit a label
@@ -36,50 +59,187 @@ pub enum LabelSubCommands {
         /// Label id
         id: String,
     },
+
+    /// Export all labels along with the recipes they tag
+    Export {
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = LabelExportFormat::Json)]
+        format: LabelExportFormat,
+    },
+}
+
+/// Formats labels can be exported to
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum LabelExportFormat {
+    /// A list of labels, each holding the list of recipes it tags
+    Json,
+
+    /// One line per label and tagged recipe pair
+    Csv,
+}
+
+/// Line of a CSV label export
+#[derive(Serialize)]
+struct Membership<'a> {
+    label_id: &'a str,
+    label_name: &'a str,
+    recipe_id: &'a str,
+    recipe_name: &'a str,
+}
+
+impl<'a> Membership<'a> {
+    /// Membership of a recipe to a label. Labels without recipes get a line with empty recipe
+    /// fields
+    fn new(label: &'a Label, recipe: Option<&'a RecipeIndex>) -> Self {
+        Membership {
+            label_id: &label.id,
+            label_name: &label.name,
+            recipe_id: recipe.map_or("", |r| r.id.as_str()),
+            recipe_name: recipe.map_or("", |r| r.name.as_str()),
+        }
+    }
 }
 pub async fn actions(origin: &str, cmd: LabelSubCommands) -> Result<(), Box<dyn error::Error>> {
     match cmd {
-        LabelSubCommands::List { pattern } => label_list(origin, pattern.as_deref()).await,
-        LabelSubCommands::Show { clue } => label_show(origin, &clue).await,
-        LabelSubCommands::Create { name } => label_create(origin, &name).await,
+        LabelSubCommands::List {
+            pattern,
+            format,
+            exact,
+            columns,
+        } => label_list(origin, pattern.as_deref(), format, &exact, &columns).await,
+        LabelSubCommands::Show { clue, raw } => label_show(origin, &clue, raw).await,
+        LabelSubCommands::Create { name, format } => label_create(origin, &name, format).await,
         LabelSubCommands::Edit { clue, name } => label_edit(origin, &clue, name.as_deref()).await,
         LabelSubCommands::Delete { id } => label_delete(origin, &id).await,
+        LabelSubCommands::Export { format } => label_export(origin, format).await,
     }
 }
 
-async fn label_list(origin: &str, pattern: Option<&str>) -> Result<(), Box<dyn error::Error>> {
-    ladle::label_index(origin, pattern.unwrap_or(""))
-        .await?
+async fn label_list(
+    origin: &str,
+    pattern: Option<&str>,
+    format: OutputFormat,
+    exact: &ExactMatch,
+    columns: &Columns,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut labels = ladle::label_index(origin, pattern.unwrap_or("")).await?;
+    exact.retain(&mut labels, |l| &l.name, pattern);
+
+    if let OutputFormat::Csv = format {
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        for index in labels.iter() {
+            writer.serialize(index)?;
+        }
+        writer.flush()?;
+        return Ok(());
+    }
+
+    let rows = labels
         .iter()
-        .map(|x| println!("{}\t{}", x.id, x.name))
-        .for_each(drop);
+        .map(|l| (l.id.as_str(), l.name.as_str()))
+        .collect::<Vec<_>>();
+    let mut term = console::Term::buffered_stdout();
+    columns.write_rows(&mut term, "\t", &rows)?;
+    term.flush()?;
     Ok(())
 }
 
-async fn label_show(origin: &str, label_clue: &str) -> Result<(), Box<dyn error::Error>> {
+async fn label_show(
+    origin: &str,
+    label_clue: &str,
+    raw: bool,
+) -> Result<(), Box<dyn error::Error>> {
     let label = label_identify(origin, label_clue, false).await?;
 
+    if raw {
+        println!("{}", ladle::label_get_raw(origin, &label.id).await?);
+        return Ok(());
+    }
+
     let Label {
         id: _,
-        name: _,
+        name,
         tagged_recipes,
+        updated_at: _,
     } = ladle::label_get(origin, &label.id).await?;
 
-    tagged_recipes
-        .iter()
-        .map(|r| {
-            println!("{}\t{}", r.id, r.name);
-        })
-        .for_each(drop);
+    let mut term = console::Term::buffered_stdout();
+
+    writeln!(term, "{}\n", console::style(format!("#{}", name)).bold())?;
+
+    for recipe in tagged_recipes.iter() {
+        writeln!(term, "  - {} ({})", recipe.name, recipe.id)?;
+    }
+
+    let plural = if tagged_recipes.len() == 1 { "" } else { "s" };
+    writeln!(
+        term,
+        "\n{}",
+        console::style(format!("{} recette{}", tagged_recipes.len(), plural)).italic()
+    )?;
 
+    term.flush()?;
     Ok(())
 }
 
-async fn label_create(origin: &str, name: &str) -> Result<(), Box<dyn error::Error>> {
-    ladle::label_create(origin, name).await?;
+async fn label_export(
+    origin: &str,
+    format: LabelExportFormat,
+) -> Result<(), Box<dyn error::Error>> {
+    let (labels, failures) = ladle::bulk::fetch_labels(origin).await?;
+    for message in failures.iter() {
+        log::error!("{}", message);
+    }
+    if !failures.is_empty() {
+        return Err(Box::new(ChopstickError(format!(
+            "Failed to fetch {} labels",
+            failures.len()
+        ))));
+    }
+
+    let mut labels = labels.into_iter().collect::<Vec<_>>();
+    labels.sort_by_key(|l| unidecode(&l.name));
+
+    match format {
+        LabelExportFormat::Json => println!("{}", serde_json::to_string_pretty(&labels)?),
+        LabelExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            for label in labels.iter() {
+                if label.tagged_recipes.is_empty() {
+                    writer.serialize(Membership::new(label, None))?;
+                }
+                for recipe in label.tagged_recipes.iter() {
+                    writer.serialize(Membership::new(label, Some(recipe)))?;
+                }
+            }
+            writer.flush()?;
+        }
+    }
+
     Ok(())
 }
 
+async fn label_create(
+    origin: &str,
+    name: &str,
+    format: CreatedFormat,
+) -> Result<(), Box<dyn error::Error>> {
+    let creation = ladle::label_create_checked(origin, name).await?;
+    if !creation.created {
+        log::warn!(
+            "Label `{}` already exists ({})",
+            creation.object.name,
+            creation.object.id
+        );
+    }
+    print_created(
+        format,
+        &creation.object.id,
+        &creation.object.name,
+        &creation.object,
+    )
+}
+
 async fn label_edit(
     origin: &str,
     label_clue: &str,
@@ -106,6 +266,7 @@ pub async fn label_identify(
         name,
         id,
         tagged_recipes: _,
+        updated_at: _,
     }) = ladle::label_get(url, clue).await
     {
         return Ok(LabelIndex { id, name });
@@ -121,18 +282,24 @@ pub async fn label_identify(
         return Ok(label.to_owned());
     }
 
-    for indice in matches.iter() {
-        if indice.name == clue {
-            return Ok(indice.to_owned());
+    if let Some(indice) = find_named(&matches, |i| &i.name, clue) {
+        if indice.name != clue {
+            log::info!("Identified label `{}` from `{}`", indice.name, clue);
         }
+        return Ok(indice.to_owned());
     }
 
     if create {
         ladle::label_create(url, clue).await
-    } else {
-        Err(Box::new(ChopstickError(format!(
-            "Failed to identify label from: `{}`",
+    } else if matches.is_empty() {
+        Err(Box::new(NotFoundError(format!(
+            "No label with id/name `{}`",
             clue
         ))))
+    } else {
+        Err(Box::new(MatchingError(
+            format!("Failed to identify label from: `{}`", clue),
+            matches.iter().map(|l| l.name.clone()).collect(),
+        )))
     }
 }
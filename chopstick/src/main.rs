@@ -1,12 +1,17 @@
+mod config_actions;
 mod helpers;
 mod ingredient_actions;
 mod label_actions;
 mod maintenance_actions;
 mod recipe_actions;
 
-use clap::{Parser, Subcommand};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use config_actions::Setting;
 use log::LevelFilter;
 use simple_logger::SimpleLogger;
+use std::path::PathBuf;
+use std::time::Instant;
 
 mod error {
     use std::error::Error;
@@ -37,19 +42,96 @@ mod error {
     }
 
     impl Error for MatchingError {}
+
+    /// Error returned when a clue does not designate any entity on the server
+    #[derive(Debug)]
+    pub struct NotFoundError(pub String);
+
+    impl fmt::Display for NotFoundError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl Error for NotFoundError {}
+
+    /// Exit codes returned by chopstick, by class of failure
+    pub const EXIT_GENERIC: i32 = 1;
+    pub const EXIT_NOT_FOUND: i32 = 2;
+    pub const EXIT_AMBIGUOUS: i32 = 3;
+    pub const EXIT_NETWORK: i32 = 4;
+    pub const EXIT_NO_SERVER: i32 = 5;
+
+    /// Map an error bubbling up from a command to the exit code describing its class
+    pub fn exit_code(error: &(dyn Error + 'static)) -> i32 {
+        if error.is::<NotFoundError>() || error.is::<ladle::NotFoundError>() {
+            EXIT_NOT_FOUND
+        } else if let Some(MatchingError(_, matches)) = error.downcast_ref::<MatchingError>() {
+            if matches.is_empty() {
+                EXIT_NOT_FOUND
+            } else {
+                EXIT_AMBIGUOUS
+            }
+        } else if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+            if error.is_connect() || error.is_timeout() || error.is_request() {
+                EXIT_NETWORK
+            } else {
+                EXIT_GENERIC
+            }
+        } else {
+            EXIT_GENERIC
+        }
+    }
 }
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
+#[command(after_help = "Exit codes:
+  0  success
+  1  generic error
+  2  entity not found
+  3  ambiguous clue, matching multiple entities
+  4  network or connection failure
+  5  missing server configuration")]
 struct Cli {
     /// Turn debugging information on
     #[arg(short, long)]
     verbose: bool,
 
     /// Server URL to contact
-    #[arg(short, long)]
+    #[arg(short, long, env = "CHOPSTICK_SERVER")]
     server: Option<String>,
 
+    /// Ignore the configuration file. The server must then be given with [-s --server] or
+    /// CHOPSTICK_SERVER
+    #[arg(long)]
+    no_config: bool,
+
+    /// Read the configuration from this file instead of ~/.config/chopstick.toml. Ignored with
+    /// [--no-config]
+    #[arg(long = "config", value_name = "FILE", env = "CHOPSTICK_CONFIG")]
+    config_file: Option<PathBuf>,
+
+    /// Read data from a JSON dump file instead of a server. Commands modifying data fail
+    #[arg(long, value_name = "FILE")]
+    dump_file: Option<PathBuf>,
+
+    /// Log write requests instead of sending them to the server
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Report the number of requests issued and the time spent on them
+    #[arg(long)]
+    timings: bool,
+
+    /// Only identify entities from names matching exactly, not ignoring case and accents
+    #[arg(long)]
+    exact_names: bool,
+
+    /// Do not display progress bars. They are always hidden when stderr is not a terminal
+    #[arg(long)]
+    no_progress: bool,
+
     #[command(subcommand)]
     command: Subcommands,
 }
@@ -72,14 +154,36 @@ enum Subcommands {
     },
 
     Maintenance {
+        /// Fail at the end of the operation if any error was tolerated along the way
+        #[arg(long, global = true)]
+        strict: bool,
+
         #[command(subcommand)]
         maintenance: maintenance_actions::MaintenanceSubCommands,
     },
+
+    Config {
+        #[command(subcommand)]
+        config: config_actions::ConfigSubCommands,
+    },
+
+    /// Print the versions of chopstick and of the server
+    Version,
+}
+
+async fn version(origin: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("chopstick {}", env!("CARGO_PKG_VERSION"));
+    match ladle::server_version(origin).await? {
+        Some(version) => println!("server {}", version),
+        None => println!("server version unknown"),
+    }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() {
-    let matches = Cli::parse();
+    let arg_matches = Cli::command().get_matches();
+    let matches = Cli::from_arg_matches(&arg_matches).unwrap_or_else(|e| e.exit());
 
     if matches.verbose {
         SimpleLogger::new()
@@ -96,8 +200,40 @@ async fn main() {
     }
 
     let mut origin: Option<String> = None;
+    let mut settings: Option<config::Config> = None;
+    let mut effective: Vec<Setting> = vec![];
 
-    if let Some(mut home) = dirs::home_dir() {
+    if matches.no_config {
+        log::debug!("Skipping configuration file");
+        effective.push(Setting::new("config_file", "", "--no-config"));
+    } else if let Some(path) = matches.config_file.as_ref() {
+        let source = match arg_matches.value_source("config_file") {
+            Some(ValueSource::EnvVariable) => "CHOPSTICK_CONFIG",
+            _ => "--config",
+        };
+        match config::Config::builder()
+            .add_source(config::File::from(path.as_path()))
+            .build()
+        {
+            Ok(config) => {
+                effective.push(Setting::new(
+                    "config_file",
+                    path.to_str().unwrap_or_default(),
+                    source,
+                ));
+                settings = Some(config)
+            }
+            // The file was asked for explicitly, so failing to read it is fatal
+            Err(message) => {
+                log::error!(
+                    "Failed to load configuration file {}: {}",
+                    path.display(),
+                    message
+                );
+                std::process::exit(error::EXIT_GENERIC);
+            }
+        }
+    } else if let Some(mut home) = dirs::home_dir() {
         home.push(".config");
         home.push("chopstick");
         home.set_extension("toml");
@@ -105,35 +241,218 @@ async fn main() {
             .add_source(config::File::with_name(home.to_str().unwrap()))
             .build()
         {
-            Ok(settings) => match settings.get::<String>("default_remote") {
-                Ok(server) => origin = Some(server),
-                Err(message) => log::debug!("{:?}", message),
-            },
+            Ok(config) => {
+                effective.push(Setting::new(
+                    "config_file",
+                    home.to_str().unwrap(),
+                    "default",
+                ));
+                settings = Some(config)
+            }
+            // A missing file is expected, but a file that fails to parse is most likely a typo
+            Err(message) if home.exists() => log::error!(
+                "Ignoring configuration file {}: {}",
+                home.display(),
+                message
+            ),
             Err(message) => log::debug!("{:?}", message),
         }
     }
 
+    if let Some(settings) = settings.as_ref() {
+        match settings.get::<String>("default_remote") {
+            Ok(server) => {
+                effective.push(Setting::new("server", &server, "config file"));
+                origin = Some(server)
+            }
+            Err(message) => log::debug!("{:?}", message),
+        }
+
+        if let Ok(author) = settings.get::<String>("default_author") {
+            effective.push(Setting::new("default_author", &author, "config file"));
+            recipe_actions::set_default_author(author);
+        }
+
+        if let Ok(quantity) = settings.get::<String>("default_dependency_quantity") {
+            effective.push(Setting::new(
+                "default_dependency_quantity",
+                &quantity,
+                "config file",
+            ));
+            recipe_actions::set_default_dependency_quantity(quantity);
+        }
+
+        if let Ok(threshold) = settings.get::<usize>("fetch_threshold") {
+            effective.push(Setting::new(
+                "fetch_threshold",
+                &threshold.to_string(),
+                "config file",
+            ));
+            helpers::set_fetch_threshold(threshold);
+        }
+
+        if let Ok(exact) = settings.get::<bool>("exact_names") {
+            effective.push(Setting::new(
+                "exact_names",
+                &exact.to_string(),
+                "config file",
+            ));
+            helpers::set_exact_names(exact);
+        }
+
+        if let Ok(retry) = settings.get::<bool>("retry_empty_reads") {
+            effective.push(Setting::new(
+                "retry_empty_reads",
+                &retry.to_string(),
+                "config file",
+            ));
+            ladle::set_retry_empty(retry);
+        }
+
+        if let Ok(limit) = settings.get::<usize>("redirect_limit") {
+            effective.push(Setting::new(
+                "redirect_limit",
+                &limit.to_string(),
+                "config file",
+            ));
+            ladle::set_redirect_limit(limit);
+        }
+
+        if let Ok(enabled) = settings.get::<bool>("write_redirects") {
+            effective.push(Setting::new(
+                "write_redirects",
+                &enabled.to_string(),
+                "config file",
+            ));
+            ladle::set_write_redirects(enabled);
+        }
+
+        if let Ok(agent) = settings.get::<String>("user_agent") {
+            effective.push(Setting::new("user_agent", &agent, "config file"));
+            ladle::set_user_agent(&agent);
+        }
+
+        if let Ok(label) = settings.get::<String>("favorite_label") {
+            effective.push(Setting::new("favorite_label", &label, "config file"));
+            recipe_actions::set_favorite_label(label);
+        }
+
+        if let Ok(path) = settings.get::<PathBuf>("snapshot_directory") {
+            effective.push(Setting::new(
+                "snapshot_directory",
+                path.to_str().unwrap_or_default(),
+                "config file",
+            ));
+            recipe_actions::set_snapshot_directory(path);
+        }
+
+        if let Ok(path) = settings.get::<PathBuf>("aliases") {
+            effective.push(Setting::new(
+                "aliases",
+                path.to_str().unwrap_or_default(),
+                "config file",
+            ));
+            if let Err(message) = ingredient_actions::load_aliases(&path) {
+                log::error!(
+                    "Failed to load aliases from {}: {}",
+                    path.display(),
+                    message
+                );
+            }
+        }
+    }
+
+    if let Ok(author) = std::env::var("CHOPSTICK_AUTHOR") {
+        effective.retain(|s| s.key != "default_author");
+        effective.push(Setting::new("default_author", &author, "CHOPSTICK_AUTHOR"));
+    }
+
+    if matches.no_progress {
+        helpers::disable_progress();
+    }
+
+    if matches.exact_names {
+        effective.retain(|s| s.key != "exact_names");
+        effective.push(Setting::new("exact_names", "true", "--exact-names"));
+        helpers::set_exact_names(true);
+    }
+
     if let Some(server) = matches.server {
+        let source = match arg_matches.value_source("server") {
+            Some(ValueSource::EnvVariable) => "CHOPSTICK_SERVER",
+            _ => "--server",
+        };
+        effective.retain(|s| s.key != "server");
+        effective.push(Setting::new("server", &server, source));
         origin = Some(server.to_owned());
     }
 
+    if let Some(path) = matches.dump_file.as_deref() {
+        match maintenance_actions::load_offline(path) {
+            Ok(dump) => {
+                effective.retain(|s| s.key != "server");
+                effective.push(Setting::new("server", &dump, "--dump-file"));
+                origin = Some(dump);
+            }
+            Err(message) => {
+                log::error!("Failed to load dump from {}: {}", path.display(), message);
+                std::process::exit(error::EXIT_GENERIC);
+            }
+        }
+    }
+
+    if let Subcommands::Config { config } = matches.command {
+        if let Err(message) = config_actions::actions(&effective, config) {
+            log::error!("{}", message);
+            std::process::exit(error::EXIT_GENERIC);
+        }
+        return;
+    }
+
     if let Some(server) = origin {
         let server = server.as_str();
+        ladle::set_caching(true);
+        ladle::set_dry_run(matches.dry_run);
+        let start = Instant::now();
         let exec = match matches.command {
             Subcommands::Recipe { recipe } => recipe_actions::actions(server, recipe).await,
             Subcommands::Ingredient { ingredient } => {
                 ingredient_actions::actions(server, ingredient).await
             }
             Subcommands::Label { label } => label_actions::actions(server, label).await,
-            Subcommands::Maintenance { maintenance } => {
-                maintenance_actions::actions(server, maintenance).await
-            }
+            Subcommands::Maintenance {
+                strict,
+                maintenance,
+            } => maintenance_actions::actions(server, maintenance, strict).await,
+            Subcommands::Version => version(server).await,
+            Subcommands::Config { .. } => unreachable!(),
         };
 
+        let summary = format!(
+            "{} requests, {:.2?} spent in requests, {:.2?} total",
+            ladle::request_count(),
+            ladle::request_time(),
+            start.elapsed()
+        );
+        if matches.timings {
+            log::info!("{}", summary);
+        } else {
+            log::debug!("{}", summary);
+        }
+
         if let Err(message) = exec {
-            log::error!("{}", message);
+            let code = error::exit_code(message.as_ref());
+            match code {
+                // Remind which server was queried, in case it is not the one intended
+                error::EXIT_NOT_FOUND | error::EXIT_AMBIGUOUS | error::EXIT_NETWORK => {
+                    log::error!("{} (server: {})", message, server)
+                }
+                _ => log::error!("{}", message),
+            }
+            std::process::exit(code);
         }
     } else {
-        log::error!("Missing parameter: [-s --server] server");
+        log::error!("Missing parameter: [-s --server] server, CHOPSTICK_SERVER or --dump-file");
+        std::process::exit(error::EXIT_NO_SERVER);
     }
 }
@@ -1,19 +1,50 @@
+use crate::error::ChopstickError;
+use crate::helpers::{normalize_name, progress_bar};
+use crate::recipe_actions::recipe_identify;
 use clap::Subcommand;
 use futures::future::join_all;
+use futures::stream::{self, StreamExt};
 use ladle::models::{Dependency, Ingredient, Label, LabelIndex, Recipe, RecipeIndex};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeSet, HashMap, HashSet};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::error;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use unidecode::unidecode;
 
 /// Maintenance commands
 #[derive(Subcommand)]
 pub enum MaintenanceSubCommands {
     /// Dump a server's contents to a JSON portable format, with stripped IDs
-    Dump,
+    Dump {
+        /// Only dump the entities modified on or after this date, formatted as YYYY-MM-DD.
+        /// Entities the server does not timestamp are left out
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        since: Option<String>,
+
+        /// Insert this prefix in the stripped IDs, as in `__PREFIX_recipe_0`, to tell apart the
+        /// entities of dumps from different sources
+        #[arg(short, long)]
+        prefix: Option<String>,
+
+        /// Leave out the fields the server computes back on clone: the recipes an ingredient is
+        /// used in, the recipes a label tags and the classifications of recipes
+        #[arg(long)]
+        flat: bool,
+    },
+    /// Write every recipe of the server to its own file in a ZIP archive, along with an index
+    Archive {
+        /// Archive to create
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+
+        /// Format of the recipe files
+        #[arg(short, long, value_enum, default_value_t = ArchiveFormat::Markdown)]
+        format: ArchiveFormat,
+    },
     /// Remove unused ingredients and tags from the server
     Clean,
     /// Clone the contents of the server or a JSON dump file to a specified remote
@@ -24,23 +55,149 @@ pub enum MaintenanceSubCommands {
 
         /// Remote server to clone the data to
         remote: String,
+
+        /// Record the entities created on the remote to this file as the clone progresses
+        #[arg(short, long, value_name = "FILE")]
+        checkpoint: Option<PathBuf>,
+
+        /// Resume an interrupted clone from a checkpoint file, skipping the entities it lists.
+        /// The checkpoint keeps being updated
+        #[arg(short, long, value_name = "FILE", conflicts_with = "checkpoint")]
+        resume: Option<PathBuf>,
+
+        /// Reuse ingredients existing on the remote under a name differing only by case or accents
+        #[arg(short, long)]
+        dedup_ingredients: bool,
+    },
+    /// Copy a recipe to a specified remote, along with the recipes and ingredients it requires
+    CopyRecipe {
+        /// Recipe name, id or identifying pattern
+        clue: String,
+
+        /// Remote server to copy the recipe to
+        #[arg(short, long)]
+        remote: String,
     },
+    /// Check that the server holds the same data as a JSON dump file
+    Verify {
+        /// JSON dump file to compare against
+        file: PathBuf,
+    },
+    /// Report requirements and dependencies pointing to deleted ingredients or recipes
+    Repair {
+        /// Remove the dangling requirements and dependencies
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Print the recipes of the server by tier of the dependency order used when cloning
+    Tiers,
+    /// Report recipes, ingredients and labels sharing a name, ignoring case and accents
+    Collisions,
+    /// Print the JSON schema of a data type
+    #[cfg(feature = "schema")]
+    Schema {
+        /// Type to describe
+        #[arg(value_enum)]
+        kind: SchemaKind,
+    },
+}
+
+/// Formats of the recipe files of an archive
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum ArchiveFormat {
+    /// A Markdown document per recipe, listing its ingredients and numbered directions
+    Markdown,
+
+    /// The recipes as returned by the server
+    Json,
+}
+
+/// Data types with a JSON schema
+#[cfg(feature = "schema")]
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum SchemaKind {
+    Recipe,
+    Ingredient,
+    Label,
+    Dump,
+}
+
+/// Number of errors maintenance commands carried on from
+static TOLERATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Log an error the current operation carries on from. In strict mode, the command fails at the
+/// end if any were raised.
+fn tolerate(message: String) {
+    log::error!("{}", message);
+    TOLERATED.fetch_add(1, Ordering::Relaxed);
 }
 
 pub async fn actions(
     origin: &str,
     cmd: MaintenanceSubCommands,
+    strict: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    maintenance(origin, cmd).await?;
+
+    let tolerated = TOLERATED.load(Ordering::Relaxed);
+    if strict && tolerated > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "{} errors were tolerated during the operation",
+            tolerated
+        ))));
+    }
+
+    Ok(())
+}
+
+async fn maintenance(
+    origin: &str,
+    cmd: MaintenanceSubCommands,
 ) -> Result<(), Box<dyn error::Error>> {
     match cmd {
-        MaintenanceSubCommands::Dump => dump(origin).await,
+        MaintenanceSubCommands::Dump {
+            since,
+            prefix,
+            flat,
+        } => dump(origin, since.as_deref(), prefix.as_deref(), flat).await,
+        MaintenanceSubCommands::Archive { output, format } => {
+            archive(origin, &output, format).await
+        }
         MaintenanceSubCommands::Clean => clean(origin).await,
-        MaintenanceSubCommands::Clone { file, remote } => {
-            clone(origin, file.as_deref(), &remote).await
+        MaintenanceSubCommands::Clone {
+            file,
+            remote,
+            checkpoint,
+            resume,
+            dedup_ingredients,
+        } => {
+            let options = CloneOptions {
+                checkpoint_path: checkpoint.as_deref().or(resume.as_deref()),
+                dedup_ingredients,
+            };
+            clone(
+                origin,
+                file.as_deref(),
+                &remote,
+                resume.as_deref(),
+                &options,
+            )
+            .await
+        }
+        MaintenanceSubCommands::CopyRecipe { clue, remote } => {
+            copy_recipe(origin, &clue, &remote).await
         }
+        MaintenanceSubCommands::Verify { file } => verify(origin, &file).await,
+        MaintenanceSubCommands::Repair { fix } => repair(origin, fix).await,
+        MaintenanceSubCommands::Tiers => tiers(origin).await,
+        MaintenanceSubCommands::Collisions => collisions(origin).await,
+        #[cfg(feature = "schema")]
+        MaintenanceSubCommands::Schema { kind } => schema(kind),
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 struct Datadump {
     recipes: Vec<Recipe>,
     ingredients: Vec<Ingredient>,
@@ -48,7 +205,104 @@ struct Datadump {
 }
 
 impl Datadump {
-    fn strip(&mut self) {
+    fn load(path: &Path) -> Result<Self, Box<dyn error::Error>> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Keep only the entities modified on or after `since`. Entities without a modification time
+    /// are dropped with a warning. References to entities left out are kept as they are, with a
+    /// warning: strip the dump beforehand for them to use the IDs of a full dump.
+    fn retain_since(&mut self, since: &str) {
+        let mut untimestamped = 0;
+        let mut modified_since = |updated_at: &Option<String>| match updated_at {
+            Some(date) => date.as_str() >= since,
+            None => {
+                untimestamped += 1;
+                false
+            }
+        };
+
+        self.recipes.retain(|r| modified_since(&r.updated_at));
+        self.ingredients.retain(|i| modified_since(&i.updated_at));
+        self.labels.retain(|l| modified_since(&l.updated_at));
+
+        if untimestamped > 0 {
+            log::warn!(
+                "Excluded {} entities without a modification time from the dump",
+                untimestamped
+            );
+        }
+
+        let external = self.external_references();
+        if external > 0 {
+            log::warn!(
+                "Kept {} references to entities left out of the dump",
+                external
+            );
+        }
+    }
+
+    /// Number of requirements, dependencies and tags referencing an entity absent from the dump
+    fn external_references(&self) -> usize {
+        let recipes: HashSet<&str> = self.recipes.iter().map(|r| r.id.as_str()).collect();
+        let ingredients: HashSet<&str> = self.ingredients.iter().map(|i| i.id.as_str()).collect();
+        let labels: HashSet<&str> = self.labels.iter().map(|l| l.id.as_str()).collect();
+
+        self.recipes
+            .iter()
+            .map(|recipe| {
+                let requirements = recipe
+                    .requirements
+                    .iter()
+                    .filter(|r| !ingredients.contains(r.ingredient.id.as_str()))
+                    .count();
+                let dependencies = recipe
+                    .dependencies
+                    .iter()
+                    .filter(|d| !recipes.contains(d.recipe.id.as_str()))
+                    .count();
+                let tags = recipe
+                    .tags
+                    .iter()
+                    .filter(|t| !labels.contains(t.id.as_str()))
+                    .count();
+
+                requirements + dependencies + tags
+            })
+            .sum()
+    }
+
+    /// Serialize the dump without the fields computed by the server. They are filled with their
+    /// default values when the dump is loaded, and ignored on clone
+    fn to_flat_json(&self) -> Result<Value, Box<dyn error::Error>> {
+        let mut document = serde_json::to_value(self)?;
+
+        for (list, field) in [
+            ("recipes", "classifications"),
+            ("ingredients", "used_in"),
+            ("labels", "tagged_recipes"),
+        ] {
+            if let Some(entities) = document[list].as_array_mut() {
+                for entity in entities.iter_mut().filter_map(Value::as_object_mut) {
+                    entity.remove(field);
+                }
+            }
+        }
+
+        Ok(document)
+    }
+
+    /// Replace the IDs with synthetic ones, as in `__recipe_0`, or `__PREFIX_recipe_0` with a
+    /// prefix. Reverse links from ingredients and labels to recipes are cleared, as they would
+    /// reference the original IDs
+    fn strip(&mut self, prefix: Option<&str>) {
+        let prefix = match prefix {
+            Some(prefix) => format!("__{}_", prefix),
+            None => String::from("__"),
+        };
+
         let mut recipe_counter: u32 = 0;
         let mut ingredient_counter: u32 = 0;
         let mut label_counter: u32 = 0;
@@ -58,7 +312,7 @@ impl Datadump {
         let mut label_table = HashMap::new();
 
         for mut label in self.labels.iter_mut() {
-            let new_id = format!("__label_{}", label_counter);
+            let new_id = format!("{}label_{}", prefix, label_counter);
             label_counter += 1;
             label_table.insert(label.id.clone(), new_id.clone());
             label.id = new_id;
@@ -66,7 +320,7 @@ impl Datadump {
         }
 
         for mut ingredient in self.ingredients.iter_mut() {
-            let new_id = format!("__ingredient_{}", ingredient_counter);
+            let new_id = format!("{}ingredient_{}", prefix, ingredient_counter);
             ingredient_counter += 1;
             ingredient_table.insert(ingredient.id.clone(), new_id.clone());
             ingredient.id = new_id;
@@ -74,7 +328,7 @@ impl Datadump {
         }
 
         for mut recipe in self.recipes.iter_mut() {
-            let new_id = format!("__recipe_{}", recipe_counter);
+            let new_id = format!("{}recipe_{}", prefix, recipe_counter);
             recipe_counter += 1;
             strip_ids(&mut recipe, &recipe_table, &ingredient_table, &label_table);
             recipe_table.insert(recipe.id.clone(), new_id.clone());
@@ -83,11 +337,162 @@ impl Datadump {
     }
 }
 
+/// Translation of the ids of cloned entities to the ids they were given on the remote. Saved to
+/// disk to allow resuming an interrupted clone.
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct Checkpoint {
+    ingredients: HashMap<String, String>,
+    recipes: HashMap<String, String>,
+}
+
+impl Checkpoint {
+    fn load(path: &Path) -> Result<Self, Box<dyn error::Error>> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    fn write(&self, path: &Path) -> Result<(), Box<dyn error::Error>> {
+        let file = File::create(path)?;
+        serde_json::to_writer(file, self)?;
+        Ok(())
+    }
+
+    /// Save the checkpoint if a path was given. Failing to do so does not interrupt the clone
+    fn save(&self, path: Option<&Path>) {
+        if let Some(path) = path {
+            if let Err(message) = self.write(path) {
+                tolerate(format!(
+                    "Failed to save checkpoint to {}: {}",
+                    path.display(),
+                    message
+                ));
+            }
+        }
+    }
+}
+
+/// Settings of a clone operation
+#[derive(Default)]
+struct CloneOptions<'a> {
+    /// File the checkpoint is saved to as the clone progresses
+    checkpoint_path: Option<&'a Path>,
+
+    /// Map ingredients to existing remote ingredients with the same normalized name
+    dedup_ingredients: bool,
+}
+
+/// Answer read requests from the contents of a JSON dump file instead of a server. Returns the
+/// origin to address the requests to.
+pub fn load_offline(path: &Path) -> Result<String, Box<dyn error::Error>> {
+    let dump = Datadump::load(path)?;
+    let origin = format!("dump:{}", path.display());
+    ladle::set_offline(&origin, dump.recipes, dump.ingredients, dump.labels);
+    Ok(origin)
+}
+
+/// Outcome of a clone operation
+#[derive(Debug, Default)]
+struct CloneReport {
+    recipes_created: usize,
+    ingredients_created: usize,
+    requirements_added: usize,
+    tags_applied: usize,
+    dependencies_linked: usize,
+
+    /// Names of the recipes created with missing tags, requirements or dependencies
+    incomplete: Vec<String>,
+
+    /// Description of every operation that failed on the remote
+    failures: Vec<String>,
+}
+
+/// Outcome of the clone of a single recipe
+#[derive(Debug, Default)]
+struct RecipeClone {
+    /// Id of the created recipe
+    id: String,
+
+    tags_applied: usize,
+    requirements_added: usize,
+    dependencies_linked: usize,
+
+    /// Names of the labels, ingredients and recipes whose tag, requirement or dependency could not
+    /// be created
+    failed_tags: Vec<String>,
+    failed_requirements: Vec<String>,
+    failed_dependencies: Vec<String>,
+
+    /// Description of every operation that failed on the remote
+    failures: Vec<String>,
+}
+
+impl RecipeClone {
+    fn is_complete(&self) -> bool {
+        self.failed_tags.is_empty()
+            && self.failed_requirements.is_empty()
+            && self.failed_dependencies.is_empty()
+    }
+}
+
+impl CloneReport {
+    fn failure(&mut self, message: String) {
+        log::error!("{}", message);
+        self.failures.push(message);
+    }
+
+    /// Account for the clone of `recipe`, logging its failures
+    fn record(&mut self, recipe: &Recipe, outcome: &RecipeClone) {
+        self.recipes_created += 1;
+        self.tags_applied += outcome.tags_applied;
+        self.requirements_added += outcome.requirements_added;
+        self.dependencies_linked += outcome.dependencies_linked;
+
+        outcome
+            .failures
+            .iter()
+            .for_each(|message| self.failure(message.clone()));
+
+        if !outcome.is_complete() {
+            self.incomplete.push(recipe.name.clone());
+        }
+    }
+
+    /// Print a summary of the clone, and turn failures into an error
+    fn conclude(&self) -> Result<(), Box<dyn error::Error>> {
+        let mut term = console::Term::buffered_stdout();
+        writeln!(term, "Recipes created:        {}", self.recipes_created)?;
+        writeln!(term, "Ingredients created:    {}", self.ingredients_created)?;
+        writeln!(term, "Requirements added:     {}", self.requirements_added)?;
+        writeln!(term, "Tags applied:           {}", self.tags_applied)?;
+        writeln!(term, "Dependencies linked:    {}", self.dependencies_linked)?;
+        writeln!(term, "Incomplete recipes:     {}", self.incomplete.len())?;
+        for name in self.incomplete.iter() {
+            writeln!(term, "  - {}", name)?;
+        }
+        writeln!(term, "Failures:               {}", self.failures.len())?;
+        term.flush()?;
+
+        if self.failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Box::new(ChopstickError(format!(
+                "Clone finished with {} failures",
+                self.failures.len()
+            ))))
+        }
+    }
+}
+
 /// Dump all data from the remote
 async fn dump_remote(origin: &str) -> Result<Datadump, Box<dyn error::Error>> {
-    let origin_recipes = fetch_recipes(origin).await?;
-    let origin_ingredients = fetch_ingredients(origin).await?;
-    let origin_labels = fetch_labels(origin).await?;
+    let ladle::bulk::Everything {
+        recipes: origin_recipes,
+        ingredients: origin_ingredients,
+        labels: origin_labels,
+        failures,
+    } = ladle::bulk::fetch_all(origin).await?;
+    failures.into_iter().for_each(tolerate);
 
     let mut dump = Datadump::default();
 
@@ -115,76 +520,61 @@ async fn dump_remote(origin: &str) -> Result<Datadump, Box<dyn error::Error>> {
     Ok(dump)
 }
 
-async fn fetch_recipes(origin: &str) -> Result<HashSet<Recipe>, Box<dyn error::Error>> {
-    let origin_index = ladle::recipe_index(origin, "").await?;
-
-    let origin_recipes_fetches = origin_index
-        .iter()
-        .map(|r| ladle::recipe_get(origin, &r.id));
-
-    let recipe_list = join_all(origin_recipes_fetches)
-        .await
-        .iter()
-        .filter_map(|response| match response {
-            Ok(recipe) => Some(recipe.to_owned()),
-            Err(message) => {
-                log::error!("{}", message);
-                None
-            }
-        })
-        .collect();
-
-    Ok(recipe_list)
+/// Keep the fetched entities, carrying on from the failed fetches
+fn tolerate_failures<T>((fetched, failures): (HashSet<T>, Vec<String>)) -> HashSet<T> {
+    failures.into_iter().for_each(tolerate);
+    fetched
 }
 
-async fn fetch_ingredients(origin: &str) -> Result<HashSet<Ingredient>, Box<dyn error::Error>> {
-    let origin_index = ladle::ingredient_index(origin, "").await?;
-
-    let origin_ingredients_fetches = origin_index
-        .iter()
-        .map(|r| ladle::ingredient_get(origin, &r.id));
-
-    let ingredient_list = join_all(origin_ingredients_fetches)
-        .await
-        .iter()
-        .filter_map(|response| match response {
-            Ok(ingredient) => Some(ingredient.to_owned()),
-            Err(message) => {
-                log::error!("{}", message);
-                None
-            }
-        })
-        .collect();
-
-    Ok(ingredient_list)
+pub async fn fetch_recipes(origin: &str) -> Result<HashSet<Recipe>, Box<dyn error::Error>> {
+    Ok(tolerate_failures(ladle::bulk::fetch_recipes(origin).await?))
 }
 
-async fn fetch_labels(origin: &str) -> Result<HashSet<Label>, Box<dyn error::Error>> {
-    let origin_index = ladle::label_index(origin, "").await?;
-
-    let origin_labels_fetches = origin_index.iter().map(|r| ladle::label_get(origin, &r.id));
-
-    let label_list = join_all(origin_labels_fetches)
-        .await
-        .iter()
-        .filter_map(|response| match response {
-            Ok(label) => Some(label.to_owned()),
-            Err(message) => {
-                log::error!("{}", message);
-                None
-            }
-        })
-        .collect();
-
-    Ok(label_list)
+pub async fn fetch_ingredients(origin: &str) -> Result<HashSet<Ingredient>, Box<dyn error::Error>> {
+    Ok(tolerate_failures(
+        ladle::bulk::fetch_ingredients(origin).await?,
+    ))
 }
 
-/// From a list of recipes, create all referenced ingredients on the remote and output a
-/// HashMap of the indexes
-async fn gen_ingredient_table<'a>(remote: &str, data: &'a Datadump) -> HashMap<&'a str, String> {
-    let mut table: HashMap<&str, String> = HashMap::new();
+/// From a list of recipes, create all referenced ingredients on the remote and record their
+/// indexes in the checkpoint. Ingredients already in the checkpoint are skipped.
+async fn gen_ingredient_table(
+    remote: &str,
+    data: &Datadump,
+    checkpoint: &mut Checkpoint,
+    options: &CloneOptions<'_>,
+    report: &mut CloneReport,
+) -> Result<(), Box<dyn error::Error>> {
+    let existing = if options.dedup_ingredients {
+        ladle::ingredient_index(remote, "")
+            .await?
+            .into_iter()
+            .map(|i| (normalize_name(&i.name), i))
+            .collect::<HashMap<_, _>>()
+    } else {
+        HashMap::new()
+    };
 
     for ingredient in data.ingredients.iter() {
+        if checkpoint.ingredients.contains_key(&ingredient.id) {
+            log::debug!("Skipping ingredient `{}`: already cloned", ingredient.name);
+            continue;
+        }
+
+        if let Some(duplicate) = existing.get(&normalize_name(&ingredient.name)) {
+            log::info!(
+                "Reusing ingredient `{}` ({}) for `{}`",
+                duplicate.name,
+                duplicate.id,
+                ingredient.name
+            );
+            checkpoint
+                .ingredients
+                .insert(ingredient.id.clone(), duplicate.id.clone());
+            checkpoint.save(options.checkpoint_path);
+            continue;
+        }
+
         match ladle::ingredient_create(
             remote,
             &ingredient.name as &str,
@@ -195,22 +585,45 @@ async fn gen_ingredient_table<'a>(remote: &str, data: &'a Datadump) -> HashMap<&
         )
         .await
         {
-            Ok(created) => table
-                .insert(&ingredient.id as &str, created.id.to_owned())
-                .unwrap_or_default(),
-            Err(message) => {
-                log::error!("{}", message);
-                String::default()
+            Ok(created) => {
+                report.ingredients_created += 1;
+                checkpoint
+                    .ingredients
+                    .insert(ingredient.id.clone(), created.id.to_owned());
+                checkpoint.save(options.checkpoint_path);
             }
+            Err(message) => report.failure(format!(
+                "Error creating ingredient `{}`: {}",
+                ingredient.name, message
+            )),
         };
     }
 
-    table
+    Ok(())
 }
 
 /// Split a set of recipes into tiers: recipes in a tier only depend on recipes in the tiers
-/// before.
+/// before. Recipes depending on a cycle or on recipes outside the set cannot be tiered: they are
+/// logged and put in a last tier.
 fn recipe_tiers<'a>(recipe_set: &'a HashSet<Recipe>) -> Vec<HashSet<&'a Recipe>> {
+    let (mut tiers, untiered) = tier_recipes(recipe_set);
+
+    if !untiered.is_empty() {
+        for recipe in untiered.iter() {
+            tolerate(format!(
+                "Recipe `{}` depends on a cycle or a missing recipe",
+                recipe.name
+            ));
+        }
+        tiers.push(untiered);
+    }
+
+    tiers
+}
+
+/// Split a set of recipes into tiers: recipes in a tier only depend on recipes in the tiers
+/// before. Also returns the recipes that cannot be tiered.
+fn tier_recipes(recipe_set: &HashSet<Recipe>) -> (Vec<HashSet<&Recipe>>, HashSet<&Recipe>) {
     // Initialize tiers with the recipes having no dependencies
     let (basic_recipes, mut rest): (HashSet<&Recipe>, HashSet<&Recipe>) =
         recipe_set.iter().partition(|r| r.dependencies.len() == 0);
@@ -241,6 +654,11 @@ fn recipe_tiers<'a>(recipe_set: &'a HashSet<Recipe>) -> Vec<HashSet<&'a Recipe>>
                 dependencies.is_subset(&tiered)
             });
 
+        // No recipe left can be tiered: they depend on a cycle or on recipes outside the set
+        if new_tier.is_empty() {
+            break;
+        }
+
         tiers.push(new_tier);
         tiered.extend(
             tiers
@@ -254,26 +672,33 @@ fn recipe_tiers<'a>(recipe_set: &'a HashSet<Recipe>) -> Vec<HashSet<&'a Recipe>>
         rest = new_rest;
     }
 
-    tiers
+    (tiers, rest)
 }
 
 /// Clone fully a recipe. Translate ingredient hashes with the ingredient table, translate
-/// dependency hashes with the recipe table. Assumes all dependencies are present on the remote.
+/// dependency hashes with the recipe table. Failing to create the recipe is an error, while the
+/// tags, requirements and dependencies that could not be created are recorded in the outcome.
 async fn recipe_clone(
     remote: &str,
     recipe: &Recipe,
-    ingredient_table: &HashMap<&str, String>,
-    recipe_table: &HashMap<&str, String>,
-) -> String {
-    let remote_recipe = ladle::recipe_create(
+    ingredient_table: &HashMap<String, String>,
+    recipe_table: &HashMap<String, String>,
+) -> Result<RecipeClone, Box<dyn error::Error>> {
+    let remote_recipe = ladle::recipe_create_extra(
         remote,
         &recipe.name,
         &recipe.author,
         &recipe.directions,
         &recipe.information,
+        recipe.image.as_deref(),
+        &serde_json::Map::default(),
     )
-    .await
-    .expect("Failed to create recipe on remote");
+    .await?;
+
+    let mut outcome = RecipeClone {
+        id: remote_recipe.id.clone(),
+        ..Default::default()
+    };
 
     let recipe_tags: Vec<&LabelIndex> = recipe.tags.iter().collect();
 
@@ -281,21 +706,18 @@ async fn recipe_clone(
         .iter()
         .map(|l| ladle::recipe_tag(remote, &remote_recipe.id, l.name.as_str()));
 
-    join_all(tag_creations)
-        .await
-        .iter()
-        .enumerate()
-        .map(|(index, response)| {
-            if let Err(message) = response {
-                log::error!(
+    for (index, response) in join_all(tag_creations).await.iter().enumerate() {
+        match response {
+            Ok(()) => outcome.tags_applied += 1,
+            Err(message) => {
+                outcome.failed_tags.push(recipe_tags[index].name.clone());
+                outcome.failures.push(format!(
                     "Error tagging recipe {} with label {}: {}",
-                    recipe.name,
-                    recipe_tags[index].name,
-                    message
-                )
+                    recipe.name, recipe_tags[index].name, message
+                ))
             }
-        })
-        .for_each(drop);
+        }
+    }
 
     let (recipe_requirements, rejected): (Vec<_>, Vec<_>) = recipe
         .requirements
@@ -303,153 +725,275 @@ async fn recipe_clone(
         .partition(|r| ingredient_table.contains_key(r.ingredient.id.as_str()));
 
     for requirement in rejected.iter() {
-        log::error!(
+        outcome
+            .failed_requirements
+            .push(requirement.ingredient.name.clone());
+        outcome.failures.push(format!(
             "Cannot create requirement of `{}` for `{}`: ingredient not mapped on target remote",
-            requirement.ingredient.name,
-            recipe.name
-        )
+            requirement.ingredient.name, recipe.name
+        ))
     }
 
     let requirement_creations = recipe_requirements.iter().map(|r| {
         let remote_ingredient_id = ingredient_table.get(r.ingredient.id.as_str()).unwrap();
-        ladle::requirement_create(
-            remote,
-            remote_recipe.id.as_str(),
-            remote_ingredient_id.as_str(),
-            &r.quantity,
-            r.optional,
-        )
+        let requirement = ladle::NewRequirement {
+            recipe_id: &remote_recipe.id,
+            ingredient_id: remote_ingredient_id,
+            quantity: &r.quantity,
+            optional: r.optional,
+        };
+        ladle::requirement_add(remote, requirement)
     });
 
-    join_all(requirement_creations)
-        .await
-        .iter()
-        .enumerate()
-        .map(|(index, response)| {
-            if let Err(message) = response {
-                log::error!(
+    for (index, response) in join_all(requirement_creations).await.iter().enumerate() {
+        match response {
+            Ok(()) => outcome.requirements_added += 1,
+            Err(message) => {
+                let name = &recipe_requirements[index].ingredient.name;
+                outcome.failed_requirements.push(name.clone());
+                outcome.failures.push(format!(
                     "Error adding requirement of `{}` for `{}`: {}",
-                    recipe_requirements[index].ingredient.name,
-                    recipe.name,
-                    message
-                )
+                    name, recipe.name, message
+                ))
             }
-        })
-        .for_each(drop);
+        }
+    }
 
-    let dependencies =
-        recipe
-            .dependencies
-            .iter()
-            .filter_map(|d| match recipe_table.get(d.recipe.id.as_str()) {
-                Some(remote_dependency_id) => Some(ladle::dependency_create(
-                    remote,
-                    remote_recipe.id.as_str(),
-                    remote_dependency_id.as_str(),
-                    &d.quantity.as_str(),
-                    d.optional,
-                )),
-                None => None,
-            });
-    join_all(dependencies)
-        .await
+    let (recipe_dependencies, rejected): (Vec<_>, Vec<_>) = recipe
+        .dependencies
         .iter()
-        .map(|response| {
-            if let Err(message) = response {
-                log::error!("{:?}", message)
+        .partition(|d| recipe_table.contains_key(d.recipe.id.as_str()));
+
+    for dependency in rejected.iter() {
+        outcome
+            .failed_dependencies
+            .push(dependency.recipe.name.clone());
+        outcome.failures.push(format!(
+            "Dropping dependency of `{}` on `{}`: recipe not mapped on target remote",
+            recipe.name, dependency.recipe.name
+        ))
+    }
+
+    let dependency_creations = recipe_dependencies.iter().map(|d| {
+        let remote_dependency_id = recipe_table.get(d.recipe.id.as_str()).unwrap();
+        let dependency = ladle::NewDependency {
+            recipe_id: &remote_recipe.id,
+            required_id: remote_dependency_id,
+            quantity: &d.quantity,
+            optional: d.optional,
+        };
+        ladle::dependency_add(remote, dependency)
+    });
+
+    for (index, response) in join_all(dependency_creations).await.iter().enumerate() {
+        match response {
+            Ok(()) => outcome.dependencies_linked += 1,
+            Err(message) => {
+                let name = &recipe_dependencies[index].recipe.name;
+                outcome.failed_dependencies.push(name.clone());
+                outcome.failures.push(format!(
+                    "Error adding dependency on `{}` to `{}`: {}",
+                    name, recipe.name, message
+                ))
             }
-        })
-        .for_each(drop);
+        }
+    }
 
-    remote_recipe.id
+    Ok(outcome)
 }
 
-async fn clone_dump(data: &Datadump, remote: &str) -> Result<(), Box<dyn error::Error>> {
-    let mut recipe_table: HashMap<&str, String> = HashMap::new();
-    let ingredient_table = gen_ingredient_table(remote, &data).await;
+async fn clone_dump(
+    data: &Datadump,
+    remote: &str,
+    checkpoint: &mut Checkpoint,
+    options: &CloneOptions<'_>,
+) -> Result<CloneReport, Box<dyn error::Error>> {
+    let mut report = CloneReport::default();
+    gen_ingredient_table(remote, data, checkpoint, options, &mut report).await?;
 
     for recipe in data.recipes.iter() {
-        let new_id = recipe_clone(remote, recipe, &ingredient_table, &recipe_table).await;
-        recipe_table.insert(recipe.id.as_str(), new_id);
+        if checkpoint.recipes.contains_key(&recipe.id) {
+            log::debug!("Skipping recipe `{}`: already cloned", recipe.name);
+            continue;
+        }
+
+        match recipe_clone(remote, recipe, &checkpoint.ingredients, &checkpoint.recipes).await {
+            Ok(outcome) => {
+                report.record(recipe, &outcome);
+                checkpoint.recipes.insert(recipe.id.clone(), outcome.id);
+                checkpoint.save(options.checkpoint_path);
+            }
+            Err(message) => report.failure(format!(
+                "Failed to create recipe `{}`: {}",
+                recipe.name, message
+            )),
+        }
     }
 
-    Ok(())
+    Ok(report)
 }
 
-/// Clone all data from one remote to the other
+/// Clone all data from one remote to the other.
+///
+/// An interrupted clone can be resumed by passing the checkpoint file it was recording to
+/// `--resume`, with the same source. Recipes are cloned as a whole, so a recipe interrupted while
+/// its requirements were being added must be fixed by hand.
 async fn clone(
     origin: &str,
     file: Option<&Path>,
     remote: &str,
+    resume: Option<&Path>,
+    options: &CloneOptions<'_>,
 ) -> Result<(), Box<dyn error::Error>> {
-    let dump;
+    let dump = match file {
+        Some(path) => Datadump::load(path)?,
+        None => dump_remote(origin).await?,
+    };
+
+    let mut checkpoint = match resume {
+        Some(path) => Checkpoint::load(path)?,
+        None => Checkpoint::default(),
+    };
+
+    clone_dump(&dump, remote, &mut checkpoint, options)
+        .await?
+        .conclude()
+}
 
-    if let Some(path) = file {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        dump = serde_json::from_reader(reader)?;
-    } else {
-        dump = dump_remote(origin).await?;
+/// Clone a recipe and its dependency tree to the remote. Only the ingredients required by the
+/// cloned recipes are created.
+async fn copy_recipe(origin: &str, clue: &str, remote: &str) -> Result<(), Box<dyn error::Error>> {
+    let recipe = recipe_identify(origin, clue).await?;
+    let recipes = ladle::recipe_tree(origin, &recipe.id)
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    let ingredient_ids = recipes
+        .iter()
+        .flat_map(|r| r.requirements.iter().map(|q| q.ingredient.id.as_str()))
+        .collect::<HashSet<_>>();
+    let fetches = ingredient_ids
+        .iter()
+        .map(|id| ladle::ingredient_get(origin, id));
+
+    let mut dump = Datadump::default();
+    for ingredient in join_all(fetches).await {
+        dump.ingredients.push(ingredient?);
     }
+    dump.recipes = recipe_tiers(&recipes)
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
 
-    clone_dump(&dump, remote).await
+    clone_dump(
+        &dump,
+        remote,
+        &mut Checkpoint::default(),
+        &CloneOptions::default(),
+    )
+    .await?
+    .conclude()
 }
 
-async fn clean(origin: &str) -> Result<(), Box<dyn error::Error>> {
-    let ingredients = ladle::ingredient_index(origin, "").await?;
+/// Copy a recipe on the same server. The copy is named `name`, or the original name followed by
+/// `suffix`. With `deep`, the recipes it depends on are copied as well, renamed with `suffix`, and
+/// the copies are wired to each other; otherwise the copy depends on the original sub-recipes.
+pub async fn duplicate_recipe(
+    origin: &str,
+    clue: &str,
+    name: Option<&str>,
+    suffix: &str,
+    deep: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let root = recipe_identify(origin, clue).await?;
 
-    let number = ingredients.len().try_into().ok().unwrap();
-    let bar = indicatif::ProgressBar::new(number)
-        .with_message("Fetching ingredients")
-        .with_style(
-            indicatif::ProgressStyle::with_template("{msg:<30} [{wide_bar}] {pos:>4}/{len:4}")
-                .unwrap()
-                .progress_chars("=>-"),
-        );
+    let recipes = if deep {
+        ladle::recipe_tree(origin, &root.id)
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>()
+    } else {
+        HashSet::from([ladle::recipe_get(origin, &root.id).await?])
+    };
 
-    let fetches = ingredients
+    let ingredient_table = recipes
         .iter()
-        .map(|ingredient| ladle::ingredient_get(origin, &ingredient.id));
+        .flat_map(|r| r.requirements.iter().map(|q| q.ingredient.id.clone()))
+        .map(|id| (id.clone(), id))
+        .collect::<HashMap<_, _>>();
 
-    let mut to_delete = HashSet::new();
-    for fetch in fetches {
-        bar.inc(1);
-        match fetch.await {
-            Err(message) => log::error!("{:?}", message),
-            Ok(ingredient) => {
-                if ingredient.used_in.len() == 0 {
-                    to_delete.insert(ingredient);
-                }
+    // Shallow copies keep depending on the original recipes
+    let (tiers, mut recipe_table) = if deep {
+        (recipe_tiers(&recipes), HashMap::new())
+    } else {
+        let table = recipes
+            .iter()
+            .flat_map(|r| r.dependencies.iter().map(|d| d.recipe.id.clone()))
+            .map(|id| (id.clone(), id))
+            .collect::<HashMap<_, _>>();
+
+        (vec![recipes.iter().collect()], table)
+    };
+
+    let mut report = CloneReport::default();
+    for recipe in tiers.into_iter().flatten() {
+        let mut copy = recipe.clone();
+        copy.name = match name {
+            Some(name) if recipe.id == root.id => name.to_string(),
+            _ => format!("{}{}", recipe.name, suffix),
+        };
+
+        match recipe_clone(origin, &copy, &ingredient_table, &recipe_table).await {
+            Ok(outcome) => {
+                report.record(&copy, &outcome);
+                log::info!(
+                    "Created `{}` ({}) from `{}`",
+                    copy.name,
+                    outcome.id,
+                    recipe.name
+                );
+                recipe_table.insert(recipe.id.clone(), outcome.id);
             }
+            Err(message) => report.failure(format!(
+                "Failed to create recipe `{}`: {}",
+                copy.name, message
+            )),
         }
     }
 
-    for ing in to_delete.iter() {
+    if !report.failures.is_empty() {
+        return Err(Box::new(ChopstickError(format!(
+            "Duplication finished with {} failures",
+            report.failures.len()
+        ))));
+    }
+
+    Ok(())
+}
+
+async fn clean(origin: &str) -> Result<(), Box<dyn error::Error>> {
+    let ingredients = ladle::ingredient_index_with_usage(origin, "").await?;
+
+    for ing in ingredients.iter().filter(|i| i.used == 0) {
         ladle::ingredient_delete(origin, ing.id.as_str()).await?;
         log::info!("Deleted ingredient `{}` ({})", ing.name, ing.id);
     }
 
-    bar.finish();
-
     let labels = ladle::label_index(origin, "").await?;
     let fetches = labels
         .iter()
         .map(|label| ladle::label_get(origin, &label.id));
 
     let number = fetches.len().try_into().ok().unwrap();
-    let bar = indicatif::ProgressBar::new(number)
-        .with_message("Fetching labels")
-        .with_style(
-            indicatif::ProgressStyle::with_template("{msg:<30} [{wide_bar}] {pos:>4}/{len:4}")
-                .unwrap()
-                .progress_chars("=>-"),
-        );
+    let bar = progress_bar(number, "Fetching labels");
 
     let mut to_delete = HashSet::new();
     for fetch in fetches {
         bar.inc(1);
         match fetch.await {
-            Err(message) => log::error!("{:?}", message),
+            Err(message) => tolerate(message.to_string()),
             Ok(label) => {
                 if label.tagged_recipes.len() == 0 {
                     to_delete.insert(label);
@@ -506,9 +1050,576 @@ fn strip_ids(
     recipe.tags = replaced_tags;
 }
 
-async fn dump(origin: &str) -> Result<(), Box<dyn error::Error>> {
+#[cfg(feature = "schema")]
+fn schema(kind: SchemaKind) -> Result<(), Box<dyn error::Error>> {
+    let schema = match kind {
+        SchemaKind::Recipe => schemars::schema_for!(Recipe),
+        SchemaKind::Ingredient => schemars::schema_for!(Ingredient),
+        SchemaKind::Label => schemars::schema_for!(Label),
+        SchemaKind::Dump => schemars::schema_for!(Datadump),
+    };
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+/// Check `date` is formatted as YYYY-MM-DD, so that it compares with RFC 3339 timestamps
+fn parse_date(date: &str) -> Result<String, String> {
+    let valid = date.len() == 10
+        && date.char_indices().all(|(index, c)| match index {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        });
+
+    if valid {
+        Ok(date.to_string())
+    } else {
+        Err(format!("`{}` is not a date formatted as YYYY-MM-DD", date))
+    }
+}
+
+async fn dump(
+    origin: &str,
+    since: Option<&str>,
+    prefix: Option<&str>,
+    flat: bool,
+) -> Result<(), Box<dyn error::Error>> {
     let mut dump = dump_remote(origin).await?;
-    dump.strip();
-    println!("{}", serde_json::to_string(&dump)?);
+    // Strip the full dump so that references to entities left out keep their stripped IDs
+    dump.strip(prefix);
+    if let Some(since) = since {
+        dump.retain_since(since);
+    }
+    if flat {
+        println!("{}", serde_json::to_string(&dump.to_flat_json()?)?);
+    } else {
+        println!("{}", serde_json::to_string(&dump)?);
+    }
+    Ok(())
+}
+
+/// Render a recipe as a Markdown document
+fn recipe_markdown(recipe: &Recipe) -> String {
+    let mut document = format!("# {}\n\n", recipe.name);
+    if !recipe.author.is_empty() {
+        document.push_str(&format!("_{}_\n\n", recipe.author));
+    }
+    if !recipe.tags.is_empty() {
+        let tags: Vec<&str> = recipe.tags.iter().map(|t| t.name.as_str()).collect();
+        document.push_str(&format!("Tags: {}\n\n", tags.join(", ")));
+    }
+
+    if !recipe.dependencies.is_empty() {
+        document.push_str("## Dependencies\n\n");
+        for dep in recipe.dependencies.iter() {
+            let optional = if dep.optional { " (optional)" } else { "" };
+            document.push_str(&format!(
+                "- {}, {}{}\n",
+                dep.recipe.name, dep.quantity, optional
+            ));
+        }
+        document.push('\n');
+    }
+
+    document.push_str("## Ingredients\n\n");
+    for req in recipe.requirements.iter() {
+        let optional = if req.optional { " (optional)" } else { "" };
+        document.push_str(&format!(
+            "- {}, {}{}\n",
+            req.ingredient.name, req.quantity, optional
+        ));
+    }
+
+    document.push_str("\n## Directions\n\n");
+    let steps = recipe
+        .directions
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty());
+    for (number, step) in steps.enumerate() {
+        document.push_str(&format!("{}. {}\n", number + 1, step));
+    }
+
+    if !recipe.information.is_empty() {
+        document.push_str(&format!("\n## Information\n\n{}\n", recipe.information));
+    }
+
+    document
+}
+
+/// Maximum number of recipes fetched ahead of the one being written to an archive
+const ARCHIVE_CONCURRENCY: usize = 8;
+
+/// Write the recipes of the server to a ZIP archive, one file per recipe named after its id, and
+/// an index listing them by name. Recipes are fetched in name order and written as they arrive,
+/// so that only a few are held in memory at once
+async fn archive(
+    origin: &str,
+    output: &Path,
+    format: ArchiveFormat,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut recipes = ladle::recipe_index(origin, "").await?;
+    recipes.sort_by_key(|r| unidecode(&r.name));
+
+    let mut archive = zip::ZipWriter::new(File::create(output)?);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let extension = match format {
+        ArchiveFormat::Markdown => "md",
+        ArchiveFormat::Json => "json",
+    };
+
+    let bar = progress_bar(recipes.len() as u64, "Archiving recipes");
+    let mut fetches = stream::iter(recipes.iter().map(|r| ladle::recipe_get(origin, &r.id)))
+        .buffered(ARCHIVE_CONCURRENCY);
+
+    let mut index = vec![];
+    while let Some(fetch) = fetches.next().await {
+        bar.inc(1);
+        let recipe = match fetch {
+            Ok(recipe) => recipe,
+            Err(message) => {
+                tolerate(message.to_string());
+                continue;
+            }
+        };
+
+        let path = format!("recipes/{}.{}", recipe.id, extension);
+        archive.start_file(path.as_str(), options)?;
+        match format {
+            ArchiveFormat::Markdown => archive.write_all(recipe_markdown(&recipe).as_bytes())?,
+            ArchiveFormat::Json => serde_json::to_writer_pretty(&mut archive, &recipe)?,
+        }
+        index.push((
+            RecipeIndex {
+                id: recipe.id,
+                name: recipe.name,
+            },
+            path,
+        ));
+    }
+    bar.finish_and_clear();
+
+    match format {
+        ArchiveFormat::Markdown => {
+            archive.start_file("index.md", options)?;
+            writeln!(archive, "# Recipes\n")?;
+            for (recipe, path) in index.iter() {
+                writeln!(archive, "- [{}]({})", recipe.name, path)?;
+            }
+        }
+        ArchiveFormat::Json => {
+            archive.start_file("index.json", options)?;
+            let entries: Vec<Value> = index
+                .iter()
+                .map(|(recipe, path)| json!({"id": recipe.id, "name": recipe.name, "path": path}))
+                .collect();
+            serde_json::to_writer_pretty(&mut archive, &entries)?;
+        }
+    }
+
+    archive.finish()?;
+    log::info!("Archived {} recipes to {}", index.len(), output.display());
+    Ok(())
+}
+
+/// Print the recipes of each tier, sorted by name, then the recipes that cannot be tiered
+async fn tiers(origin: &str) -> Result<(), Box<dyn error::Error>> {
+    let recipes = fetch_recipes(origin).await?;
+    let (tiers, untiered) = tier_recipes(&recipes);
+
+    let sorted = |tier: &HashSet<&Recipe>| {
+        let mut names = tier.iter().map(|r| r.name.clone()).collect::<Vec<_>>();
+        names.sort_by_key(|name| unidecode(name));
+        names
+    };
+
+    let mut term = console::Term::buffered_stdout();
+
+    for (index, tier) in tiers.iter().enumerate() {
+        writeln!(term, "{}", console::style(format!("Tier {}", index)).bold())?;
+        for name in sorted(tier) {
+            writeln!(term, "  - {}", name)?;
+        }
+    }
+
+    if !untiered.is_empty() {
+        writeln!(
+            term,
+            "{}",
+            console::style("Depending on a cycle or a missing recipe").bold()
+        )?;
+        for name in sorted(&untiered) {
+            writeln!(term, "  - {}", name)?;
+        }
+    }
+
+    term.flush()?;
+    Ok(())
+}
+
+/// Group the `(name, id)` pairs whose names only differ by case or accents. Pairs without a
+/// homonym are left out
+fn name_collisions(entities: Vec<(String, String)>) -> Vec<Vec<(String, String)>> {
+    let mut groups: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    for (name, id) in entities.into_iter() {
+        groups
+            .entry(normalize_name(&name))
+            .or_default()
+            .push((name, id));
+    }
+
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+/// Report the entities sharing a name, which clues cannot tell apart
+async fn collisions(origin: &str) -> Result<(), Box<dyn error::Error>> {
+    let categories = [
+        (
+            "Recipes",
+            ladle::recipe_index(origin, "")
+                .await?
+                .into_iter()
+                .map(|r| (r.name, r.id))
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "Ingredients",
+            ladle::ingredient_index(origin, "")
+                .await?
+                .into_iter()
+                .map(|i| (i.name, i.id))
+                .collect(),
+        ),
+        (
+            "Labels",
+            ladle::label_index(origin, "")
+                .await?
+                .into_iter()
+                .map(|l| (l.name, l.id))
+                .collect(),
+        ),
+    ];
+
+    let mut term = console::Term::buffered_stdout();
+    let mut found = 0;
+
+    for (title, entities) in categories.into_iter() {
+        let groups = name_collisions(entities);
+        found += groups.len();
+
+        writeln!(term, "{}", console::style(title).bold())?;
+        for group in groups.iter() {
+            let members = group
+                .iter()
+                .map(|(name, id)| format!("{} ({})", name, id))
+                .collect::<Vec<_>>();
+            writeln!(term, "  - {}", members.join(", "))?;
+        }
+        writeln!(term)?;
+    }
+
+    term.flush()?;
+    log::info!("Found {} name collisions", found);
     Ok(())
 }
+
+/// Find requirements and dependencies whose targets no longer exist on the server, and delete
+/// them if `fix` is set
+async fn repair(origin: &str, fix: bool) -> Result<(), Box<dyn error::Error>> {
+    let recipes = fetch_recipes(origin).await?;
+    let ingredient_ids = ladle::ingredient_index(origin, "")
+        .await?
+        .into_iter()
+        .map(|i| i.id)
+        .collect::<HashSet<_>>();
+    let recipe_ids = ladle::recipe_index(origin, "")
+        .await?
+        .into_iter()
+        .map(|r| r.id)
+        .collect::<HashSet<_>>();
+
+    let mut dangling = 0;
+    let mut failures = 0;
+
+    for recipe in recipes.iter() {
+        for requirement in recipe
+            .requirements
+            .iter()
+            .filter(|r| !ingredient_ids.contains(&r.ingredient.id))
+        {
+            dangling += 1;
+            log::warn!(
+                "Recipe `{}` ({}) requires missing ingredient `{}` ({})",
+                recipe.name,
+                recipe.id,
+                requirement.ingredient.name,
+                requirement.ingredient.id
+            );
+
+            if fix {
+                match ladle::requirement_delete(origin, &recipe.id, &requirement.ingredient.id)
+                    .await
+                {
+                    Ok(()) => log::info!(
+                        "Deleted requirement of `{}` from `{}`",
+                        requirement.ingredient.name,
+                        recipe.name
+                    ),
+                    Err(message) => {
+                        failures += 1;
+                        log::error!(
+                            "Failed to delete requirement of `{}` from `{}`: {}",
+                            requirement.ingredient.name,
+                            recipe.name,
+                            message
+                        )
+                    }
+                }
+            }
+        }
+
+        for dependency in recipe
+            .dependencies
+            .iter()
+            .filter(|d| !recipe_ids.contains(&d.recipe.id))
+        {
+            dangling += 1;
+            log::warn!(
+                "Recipe `{}` ({}) depends on missing recipe `{}` ({})",
+                recipe.name,
+                recipe.id,
+                dependency.recipe.name,
+                dependency.recipe.id
+            );
+
+            if fix {
+                match ladle::dependency_delete(origin, &recipe.id, &dependency.recipe.id).await {
+                    Ok(()) => log::info!(
+                        "Deleted dependency on `{}` from `{}`",
+                        dependency.recipe.name,
+                        recipe.name
+                    ),
+                    Err(message) => {
+                        failures += 1;
+                        log::error!(
+                            "Failed to delete dependency on `{}` from `{}`: {}",
+                            dependency.recipe.name,
+                            recipe.name,
+                            message
+                        )
+                    }
+                }
+            }
+        }
+    }
+
+    log::info!(
+        "Found {} dangling references in {} recipes",
+        dangling,
+        recipes.len()
+    );
+
+    if failures > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "Failed to remove {} dangling references",
+            failures
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Report the entities present on one side only or with differing contents, matching them by
+/// name. Returns the number of differences found.
+fn compare_entities<T>(
+    kind: &str,
+    expected: &[T],
+    actual: &[T],
+    name: fn(&T) -> &str,
+    contents: fn(&T) -> Value,
+) -> usize {
+    let expected = expected
+        .iter()
+        .map(|e| (name(e), contents(e)))
+        .collect::<HashMap<_, _>>();
+    let actual = actual
+        .iter()
+        .map(|e| (name(e), contents(e)))
+        .collect::<HashMap<_, _>>();
+
+    let mut differences = 0;
+
+    for (name, contents) in expected.iter() {
+        match actual.get(name) {
+            None => {
+                differences += 1;
+                log::warn!("Missing {} `{}` on server", kind, name);
+            }
+            Some(found) if found != contents => {
+                differences += 1;
+                log::warn!(
+                    "Differing {} `{}`: expected {}, found {}",
+                    kind,
+                    name,
+                    contents,
+                    found
+                );
+            }
+            Some(_) => (),
+        }
+    }
+
+    for name in actual.keys().filter(|name| !expected.contains_key(*name)) {
+        differences += 1;
+        log::warn!("Unexpected {} `{}` on server", kind, name);
+    }
+
+    differences
+}
+
+fn recipe_contents(recipe: &Recipe) -> Value {
+    json!({
+        "author": recipe.author,
+        "directions": recipe.directions,
+        "information": recipe.information,
+        "requirements": recipe
+            .requirements
+            .iter()
+            .map(|r| (&r.ingredient.name, &r.quantity, r.optional))
+            .collect::<BTreeSet<_>>(),
+        "dependencies": recipe
+            .dependencies
+            .iter()
+            .map(|d| (&d.recipe.name, &d.quantity, d.optional))
+            .collect::<BTreeSet<_>>(),
+        "tags": recipe.tags.iter().map(|t| &t.name).collect::<BTreeSet<_>>(),
+    })
+}
+
+/// Compare the contents of a dump file with the server's
+async fn verify(origin: &str, file: &Path) -> Result<(), Box<dyn error::Error>> {
+    let file = File::open(file)?;
+    let reader = BufReader::new(file);
+    let mut expected: Datadump = serde_json::from_reader(reader)?;
+    let mut actual = dump_remote(origin).await?;
+
+    expected.strip(None);
+    actual.strip(None);
+
+    let differences = compare_entities(
+        "recipe",
+        &expected.recipes,
+        &actual.recipes,
+        |r| &r.name,
+        recipe_contents,
+    ) + compare_entities(
+        "ingredient",
+        &expected.ingredients,
+        &actual.ingredients,
+        |i| &i.name,
+        |i| json!(i.classifications),
+    ) + compare_entities(
+        "label",
+        &expected.labels,
+        &actual.labels,
+        |l| &l.name,
+        |_| Value::Null,
+    );
+
+    if differences == 0 {
+        log::info!(
+            "Verification passed: {} recipes, {} ingredients, {} labels",
+            expected.recipes.len(),
+            expected.ingredients.len(),
+            expected.labels.len()
+        );
+        Ok(())
+    } else {
+        Err(Box::new(ChopstickError(format!(
+            "Verification failed: {} differences",
+            differences
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ladle::models::{IngredientIndex, Requirement};
+
+    #[test]
+    fn retain_since_keeps_stripped_references() {
+        let flour = Ingredient {
+            id: String::from("flour"),
+            name: String::from("Farine"),
+            updated_at: Some(String::from("2023-01-01T00:00:00Z")),
+            ..Default::default()
+        };
+        let base = Recipe {
+            id: String::from("dough"),
+            name: String::from("Pâte"),
+            requirements: BTreeSet::from([Requirement {
+                ingredient: IngredientIndex {
+                    id: flour.id.clone(),
+                    name: flour.name.clone(),
+                },
+                quantity: String::from("500 g"),
+                optional: false,
+            }]),
+            updated_at: Some(String::from("2023-01-01T00:00:00Z")),
+            ..Default::default()
+        };
+        let tart = Recipe {
+            id: String::from("tart"),
+            name: String::from("Tarte"),
+            dependencies: BTreeSet::from([Dependency {
+                recipe: RecipeIndex {
+                    id: base.id.clone(),
+                    name: base.name.clone(),
+                },
+                quantity: String::from("1"),
+                optional: false,
+            }]),
+            tags: BTreeSet::from([LabelIndex {
+                id: String::from("dessert"),
+                name: String::from("Dessert"),
+            }]),
+            updated_at: Some(String::from("2024-06-01T00:00:00Z")),
+            ..Default::default()
+        };
+        let dessert = Label {
+            id: String::from("dessert"),
+            name: String::from("Dessert"),
+            updated_at: Some(String::from("2023-01-01T00:00:00Z")),
+            ..Default::default()
+        };
+
+        let mut dump = Datadump {
+            recipes: vec![base.clone(), tart.clone()],
+            ingredients: vec![flour],
+            labels: vec![dessert],
+        };
+        dump.strip(None);
+        dump.retain_since("2024-01-01");
+
+        assert_eq!(dump.recipes.len(), 1);
+        assert!(dump.ingredients.is_empty());
+        assert!(dump.labels.is_empty());
+
+        let stripped = &dump.recipes[0];
+        assert_eq!(stripped.name, tart.name);
+        let dependencies: Vec<&str> = stripped
+            .dependencies
+            .iter()
+            .map(|d| d.recipe.id.as_str())
+            .collect();
+        assert_eq!(dependencies, vec!["__recipe_0"]);
+        let tags: Vec<&str> = stripped.tags.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(tags, vec!["__label_0"]);
+        assert_eq!(dump.external_references(), 2);
+    }
+}
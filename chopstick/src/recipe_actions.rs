@@ -1,11 +1,26 @@
-use crate::error::MatchingError;
-use crate::helpers::display_classifications;
+use crate::error::{ChopstickError, MatchingError, NotFoundError};
+use crate::helpers::{
+    check_fetch_count, classifications_from, display_classifications, find_named, for_each_clue,
+    normalize_name, print_created, progress_bar, read_clues, timestamp, wrap, Classification,
+    Columns, CreatedFormat, ExactMatch, OutputFormat,
+};
 use crate::ingredient_actions::ingredient_identify;
 use crate::label_actions::label_identify;
-use clap::Subcommand;
-use ladle::models::RecipeIndex;
+use crate::maintenance_actions::{duplicate_recipe, fetch_recipes};
+use clap::{Subcommand, ValueEnum};
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use ladle::models::{Classifications, RecipeIndex};
+use ladle::quantity::Quantity;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::error;
-use std::io::Write;
+use std::fs;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 use unidecode::unidecode;
 
 /// Recipe fetching and edition family of commands
@@ -15,12 +30,169 @@ pub enum RecipeSubCommands {
     List {
         /// Recipe name pattern to match in list
         pattern: Option<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        /// Refresh the listing every SECONDS until interrupted
+        #[arg(short, long, value_name = "SECONDS", num_args = 0..=1, default_missing_value = "5")]
+        watch: Option<u64>,
+
+        /// Only list recipes by this author. Requires fetching every matching recipe
+        #[arg(short, long)]
+        author: Option<String>,
+
+        /// Fetch all matching recipes when filtering by author, sorting by complexity or grouping
+        /// by tag, even above the fetch threshold
+        #[arg(long)]
+        all: bool,
+
+        /// Order of the listing. Sorting by complexity requires fetching every matching recipe
+        #[arg(long, value_enum, default_value_t = RecipeSort::Name)]
+        sort: RecipeSort,
+
+        /// Reverse the order of the listing
+        #[arg(long)]
+        reverse: bool,
+
+        /// List recipes under a heading for each of their tags. Requires fetching every matching
+        /// recipe
+        #[arg(long)]
+        group_by_tag: bool,
+
+        #[command(flatten)]
+        exact: ExactMatch,
+
+        #[command(flatten)]
+        columns: Columns,
+    },
+
+    /// List recipes no other recipe depends on. Requires fetching every recipe
+    Roots {
+        #[command(flatten)]
+        columns: Columns,
+    },
+
+    /// List recipes without dependencies. Requires fetching every recipe
+    Leaves {
+        #[command(flatten)]
+        columns: Columns,
     },
 
     /// Fetch details about a recipe
     Show {
+        /// Recipe name, id or identifying pattern
+        #[arg(required_unless_present = "clues_file")]
+        clue: Option<String>,
+
+        /// Show every recipe identified by the lines of this file instead
+        #[arg(long, value_name = "FILE", conflicts_with = "clue")]
+        clues_file: Option<PathBuf>,
+
+        /// Print a single line summary without resolving dependencies
+        #[arg(short, long)]
+        brief: bool,
+
+        /// Print the server's response as is
+        #[arg(long, conflicts_with = "brief")]
+        raw: bool,
+    },
+
+    /// Print the ingredients required by a recipe, one per line
+    Ingredients {
+        /// Recipe name, id or identifying pattern
+        clue: String,
+
+        /// Include the requirements of the recipes it depends on
+        #[arg(short, long)]
+        deep: bool,
+
+        /// Multiply all quantities by this factor
+        #[arg(short, long, default_value_t = 1.0)]
+        scale: f64,
+
+        /// Sum the quantities of an ingredient required several times, converting compatible
+        /// units to a common one. Incompatible units are listed separately
+        #[arg(short, long)]
+        normalize: bool,
+
+        /// List optional requirements, marked as such. This is the default
+        #[arg(long, overrides_with = "exclude_optional")]
+        include_optional: bool,
+
+        /// Leave optional requirements out
+        #[arg(long, overrides_with = "include_optional")]
+        exclude_optional: bool,
+    },
+
+    /// Render a recipe as plain text laid out for printing on paper
+    Print {
+        /// Recipe name, id or identifying pattern
+        clue: String,
+
+        /// Column width to wrap the text to
+        #[arg(short, long, default_value_t = 72)]
+        width: usize,
+
+        /// Multiply all quantities by this factor
+        #[arg(short, long, default_value_t = 1.0)]
+        scale: f64,
+    },
+
+    /// Estimate the cost of a recipe from a local price table
+    Cost {
+        /// Recipe name, id or identifying pattern
+        clue: String,
+
+        /// TOML file mapping ingredient names to unit prices: the price of a gram for masses, of a
+        /// millilitre for volumes and of a piece for quantities without a unit
+        #[arg(short, long, value_name = "FILE")]
+        prices: Option<PathBuf>,
+    },
+
+    /// Export a recipe to a self-contained document
+    Export {
         /// Recipe name, id or identifying pattern
         clue: String,
+
+        /// Export format
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+
+        /// Fetch every required ingredient and embed its details, including classifications
+        #[arg(short, long)]
+        resolve: bool,
+
+        /// Typst template rendering the recipe, for PDF exports. The recipe is passed as JSON in
+        /// `sys.inputs.recipe`
+        #[arg(short, long, value_name = "FILE", required_if_eq("format", "pdf"))]
+        template: Option<PathBuf>,
+
+        /// File to write the PDF to
+        #[arg(short, long, value_name = "FILE", required_if_eq("format", "pdf"))]
+        output: Option<PathBuf>,
+    },
+
+    /// Check a recipe for common issues before publishing it
+    Validate {
+        /// Recipe name, id or identifying pattern
+        #[arg(required_unless_present = "clues_file")]
+        clue: Option<String>,
+
+        /// Validate every recipe identified by the lines of this file instead
+        #[arg(long, value_name = "FILE", conflicts_with = "clue")]
+        clues_file: Option<PathBuf>,
+    },
+
+    /// Compare two recipes. Removals are marked with `-` and additions with `+`, in red and green
+    /// when the output is a terminal
+    Diff {
+        /// Name, id or identifying pattern of the original recipe
+        lhs: String,
+
+        /// Name, id or identifying pattern of the recipe to compare it to
+        rhs: String,
     },
 
     /// Create a recipe on the server
@@ -28,8 +200,8 @@ pub enum RecipeSubCommands {
         /// Recipe's name
         name: String,
 
-        /// Recipe's author
-        #[arg(short, long)]
+        /// Recipe's author, defaults to the configured `default_author`
+        #[arg(short, long, env = "CHOPSTICK_AUTHOR")]
         author: Option<String>,
 
         /// Recipe's directions
@@ -39,6 +211,83 @@ pub enum RecipeSubCommands {
         /// Recipe's information
         #[arg(short, long)]
         information: bool,
+
+        /// Read the recipe's directions from standard input
+        #[arg(long, conflicts_with_all = ["directions", "information_stdin"])]
+        directions_stdin: bool,
+
+        /// Read the recipe's information from standard input
+        #[arg(long, conflicts_with = "information")]
+        information_stdin: bool,
+
+        /// URL of a picture of the recipe
+        #[arg(long, value_name = "URL")]
+        image: Option<String>,
+
+        /// Copy the requirements of this recipe into the new one. Name, id or identifying pattern
+        #[arg(long, value_name = "CLUE")]
+        template: Option<String>,
+
+        /// Also copy the tags of the template recipe
+        #[arg(long, requires = "template")]
+        template_tags: bool,
+
+        /// Send a field ladle does not model, as KEY=VALUE. VALUE is parsed as JSON, or taken as
+        /// a string. Can be repeated
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_field)]
+        fields: Vec<(String, Value)>,
+
+        /// How to report the created recipe on standard output
+        #[arg(long, value_enum, default_value_t = CreatedFormat::Id)]
+        format: CreatedFormat,
+    },
+
+    /// Mark a recipe as a favorite, by tagging it with the favorite label
+    Favorite {
+        /// Recipe name, id or identifying pattern
+        clue: String,
+    },
+
+    /// Remove a recipe from the favorites
+    Unfavorite {
+        /// Recipe name, id or identifying pattern
+        clue: String,
+    },
+
+    /// List the favorite recipes
+    Favorites {
+        #[command(flatten)]
+        columns: Columns,
+    },
+
+    /// Save a recipe and the recipes it depends on to a local, timestamped file
+    Snapshot {
+        /// Recipe name, id or identifying pattern
+        clue: String,
+    },
+
+    /// List the local snapshots of a recipe, oldest first
+    Snapshots {
+        /// Recipe name, id or identifying pattern
+        clue: String,
+    },
+
+    /// Copy a recipe on the server
+    Duplicate {
+        /// Recipe name, id or identifying pattern
+        clue: String,
+
+        /// Name of the copy. Defaults to the original name followed by the suffix
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Also copy the recipes it depends on, and make the copy depend on those copies
+        #[arg(short, long)]
+        deep: bool,
+
+        /// Text appended to the names of the copies
+        #[arg(short, long, default_value = " (copie)")]
+        suffix: String,
     },
 
     /// Edit an existing recipe on the server
@@ -61,12 +310,47 @@ pub enum RecipeSubCommands {
         /// Change recipe information
         #[arg(short, long)]
         information: bool,
+
+        /// Change the URL of the recipe's picture
+        #[arg(long, value_name = "URL")]
+        image: Option<String>,
+
+        /// Set a field to an empty value. Can be repeated
+        #[arg(short, long, value_enum)]
+        clear: Vec<ClearableField>,
+
+        /// Send a field ladle does not model, as KEY=VALUE. VALUE is parsed as JSON, or taken as
+        /// a string. Can be repeated
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_field)]
+        fields: Vec<(String, Value)>,
     },
 
     /// Delete a recipe from the server
     Delete {
         /// Recipe id matching the recipe to delete
-        id: String,
+        #[arg(required_unless_present = "matching", conflicts_with = "matching")]
+        id: Option<String>,
+
+        /// Delete all recipes whose name matches this pattern
+        #[arg(short, long, value_name = "PATTERN")]
+        matching: Option<String>,
+
+        /// Do not ask for confirmation before deleting matching recipes or orphaned ingredients
+        #[arg(short, long)]
+        yes: bool,
+
+        /// List the matching recipes without deleting them
+        #[arg(long, requires = "matching")]
+        dry_run: bool,
+
+        /// Remove dependencies on the matching recipes from other recipes instead of refusing to
+        /// delete them
+        #[arg(long, requires = "matching")]
+        cascade: bool,
+
+        /// Also delete the ingredients of the deleted recipes no other recipe uses anymore
+        #[arg(long)]
+        gc_ingredients: bool,
     },
 
     Requirement {
@@ -85,6 +369,82 @@ pub enum RecipeSubCommands {
     },
 }
 
+/// Orders recipe listings can be sorted in
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum RecipeSort {
+    /// Alphabetical order
+    Name,
+    /// Most requirements and dependencies first
+    Complexity,
+}
+
+/// Recipe text fields that can be emptied
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ClearableField {
+    Author,
+    Directions,
+    Information,
+    Image,
+}
+
+/// Where the text of a field is read from on recipe creation
+#[derive(Clone, Copy)]
+enum TextInput {
+    Empty,
+    Editor,
+    Stdin,
+}
+
+impl TextInput {
+    fn from_flags(editor: bool, stdin: bool) -> Self {
+        match (editor, stdin) {
+            (true, _) => TextInput::Editor,
+            (false, true) => TextInput::Stdin,
+            (false, false) => TextInput::Empty,
+        }
+    }
+
+    fn read(self, prompt: &str) -> Result<String, Box<dyn error::Error>> {
+        Ok(match self {
+            TextInput::Empty => None,
+            TextInput::Editor => {
+                launch_editor(&dialoguer::Editor::new(), prompt)?.filter(|text| text != prompt)
+            }
+            TextInput::Stdin => Some(read_stdin()?),
+        }
+        .unwrap_or_default())
+    }
+}
+
+/// New values of a recipe's single line fields. Fields set to `None` are left unchanged
+struct RecipeChanges<'a> {
+    name: Option<&'a str>,
+    author: Option<&'a str>,
+    image: Option<&'a str>,
+
+    /// Fields ladle does not model, sent as is
+    extra: Map<String, Value>,
+}
+
+/// Parse a KEY=VALUE field given on the command line. Values that are not valid JSON are taken as
+/// strings
+fn parse_field(field: &str) -> Result<(String, Value), String> {
+    let (key, value) = field
+        .split_once('=')
+        .ok_or_else(|| format!("`{}` is not formatted as KEY=VALUE", field))?;
+
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    Ok((key.to_string(), value))
+}
+
+/// Document formats a recipe can be exported to
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    /// Compiled with Typst from a template
+    Pdf,
+}
+
 /// Manage a recipe's requirements
 #[derive(Subcommand)]
 pub enum RequirementSubCommands {
@@ -102,6 +462,19 @@ pub enum RequirementSubCommands {
         /// Optional status
         #[arg(short, long)]
         optional: bool,
+
+        /// Create the ingredient if it does not exist
+        #[arg(short, long)]
+        create_ingredient: bool,
+
+        /// Classifications of the ingredient if it is created
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            requires = "create_ingredient"
+        )]
+        classifications: Vec<Classification>,
     },
 
     /// Edit a requirement
@@ -129,6 +502,31 @@ pub enum RequirementSubCommands {
         /// Ingredient name, id or identifying pattern
         ingredient_clue: String,
     },
+
+    /// Make a recipe's requirements match a file. Each line of the file is formatted as
+    /// `ingredient: quantity`, optionally followed by `optional`
+    Sync {
+        /// Recipe name, id or identifying pattern
+        recipe_clue: String,
+
+        /// Path to the list of requirements
+        #[arg(long)]
+        from: PathBuf,
+    },
+
+    /// Change the optional status of every requirement of a recipe
+    SetOptional {
+        /// Recipe name, id or identifying pattern
+        recipe_clue: String,
+
+        /// Optional status to give every requirement
+        #[arg(long, action = clap::ArgAction::Set, required = true)]
+        all: bool,
+
+        /// List the requirements that would change without editing them
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 /// Manage a recipe's dependencies
@@ -142,7 +540,8 @@ pub enum DependencySubCommands {
         /// Recipe name, id or identifying pattern
         required_clue: String,
 
-        /// Required quantity
+        /// Required quantity. Defaults to `1 batch`, or the `default_dependency_quantity` setting;
+        /// pass an empty string to leave it blank
         quantity: Option<String>,
 
         /// Optional status
@@ -183,10 +582,26 @@ pub enum TagSubCommands {
     /// Add a tag
     Add {
         /// Recipe name, id or identifying pattern
-        recipe_clue: String,
+        #[arg(required_unless_present = "clues_file")]
+        recipe_clue: Option<String>,
 
         /// Label name
-        label_name: String,
+        #[arg(required_unless_present = "clues_file")]
+        label_name: Option<String>,
+
+        /// Tag every recipe identified by the lines of this file instead, with the label given by
+        /// `--label`
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["recipe_clue", "label_name"],
+            requires = "label"
+        )]
+        clues_file: Option<PathBuf>,
+
+        /// Label name to tag the recipes of `--clues-file` with
+        #[arg(long, requires = "clues_file")]
+        label: Option<String>,
     },
 
     /// Delete a tag
@@ -197,6 +612,18 @@ pub enum TagSubCommands {
         /// Label name, id or identifying pattern
         label_clue: String,
     },
+
+    /// Replace a tag with another
+    Replace {
+        /// Recipe name, id or identifying pattern
+        recipe_clue: String,
+
+        /// Label name, id or identifying pattern of the tag to remove
+        old_label_clue: String,
+
+        /// Label name of the tag to add
+        new_label_name: String,
+    },
 }
 
 pub async fn requirement_actions(
@@ -209,7 +636,19 @@ pub async fn requirement_actions(
             ingredient_clue,
             quantity,
             optional,
-        } => requirement_add(origin, &recipe_clue, &ingredient_clue, &quantity, optional).await,
+            create_ingredient,
+            classifications,
+        } => {
+            requirement_add(
+                origin,
+                &recipe_clue,
+                &ingredient_clue,
+                &quantity,
+                optional,
+                create_ingredient.then(|| classifications_from(&classifications)),
+            )
+            .await
+        }
         RequirementSubCommands::Edit {
             recipe_clue,
             ingredient_clue,
@@ -229,6 +668,14 @@ pub async fn requirement_actions(
             recipe_clue,
             ingredient_clue,
         } => requirement_delete(origin, &recipe_clue, &ingredient_clue).await,
+        RequirementSubCommands::Sync { recipe_clue, from } => {
+            requirement_sync(origin, &recipe_clue, &from).await
+        }
+        RequirementSubCommands::SetOptional {
+            recipe_clue,
+            all,
+            dry_run,
+        } => requirement_set_optional(origin, &recipe_clue, all, dry_run).await,
     }
 }
 
@@ -279,42 +726,212 @@ pub async fn tag_actions(origin: &str, cmd: TagSubCommands) -> Result<(), Box<dy
         TagSubCommands::Add {
             recipe_clue,
             label_name,
-        } => recipe_tag(origin, &recipe_clue, &label_name).await,
+            clues_file,
+            label,
+        } => match (clues_file, label, recipe_clue, label_name) {
+            (Some(path), Some(label), _, _) => {
+                let clues = read_clues(&path)?;
+                for_each_clue(&clues, |clue| recipe_tag(origin, clue, &label)).await
+            }
+            (None, _, Some(recipe_clue), Some(label_name)) => {
+                recipe_tag(origin, &recipe_clue, &label_name).await
+            }
+            _ => Err(Box::new(ChopstickError(String::from(
+                "Missing recipe clue or label name",
+            )))),
+        },
         TagSubCommands::Delete {
             recipe_clue,
             label_clue,
         } => recipe_untag(origin, &recipe_clue, &label_clue).await,
+        TagSubCommands::Replace {
+            recipe_clue,
+            old_label_clue,
+            new_label_name,
+        } => recipe_retag(origin, &recipe_clue, &old_label_clue, &new_label_name).await,
     }
 }
 
 pub async fn actions(origin: &str, cmd: RecipeSubCommands) -> Result<(), Box<dyn error::Error>> {
     match cmd {
-        RecipeSubCommands::List { pattern } => recipe_list(origin, pattern.as_deref()).await,
-        RecipeSubCommands::Show { clue } => recipe_show(origin, &clue).await,
+        RecipeSubCommands::List {
+            pattern,
+            format,
+            watch,
+            author,
+            all,
+            sort,
+            reverse,
+            group_by_tag,
+            exact,
+            columns,
+        } => {
+            let options = ListOptions {
+                author: author.as_deref(),
+                all,
+                sort,
+                reverse,
+                group_by_tag,
+                exact,
+            };
+            match watch {
+                Some(interval) => {
+                    recipe_watch(
+                        origin,
+                        pattern.as_deref(),
+                        &options,
+                        format,
+                        &columns,
+                        interval,
+                    )
+                    .await
+                }
+                None => recipe_list(origin, pattern.as_deref(), &options, format, &columns).await,
+            }
+        }
+        RecipeSubCommands::Roots { columns } => recipe_roots(origin, &columns).await,
+        RecipeSubCommands::Leaves { columns } => recipe_leaves(origin, &columns).await,
+        RecipeSubCommands::Show {
+            clue,
+            clues_file,
+            brief,
+            raw,
+        } => {
+            let clues = clues_from(clue, clues_file.as_deref())?;
+            if raw {
+                for_each_clue(&clues, |clue| recipe_show_raw(origin, clue)).await
+            } else if brief {
+                for_each_clue(&clues, |clue| recipe_show_brief(origin, clue)).await
+            } else {
+                for_each_clue(&clues, |clue| recipe_show(origin, clue)).await
+            }
+        }
+        RecipeSubCommands::Ingredients {
+            clue,
+            deep,
+            scale,
+            normalize,
+            include_optional: _,
+            exclude_optional,
+        } => recipe_ingredients(origin, &clue, deep, scale, normalize, !exclude_optional).await,
+        RecipeSubCommands::Print { clue, width, scale } => {
+            recipe_print(origin, &clue, width, scale).await
+        }
+        RecipeSubCommands::Cost { clue, prices } => {
+            recipe_cost(origin, &clue, prices.as_deref()).await
+        }
+        RecipeSubCommands::Export {
+            clue,
+            format,
+            resolve,
+            template,
+            output,
+        } => match (format, template, output) {
+            (ExportFormat::Pdf, Some(template), Some(output)) => {
+                recipe_export_pdf(origin, &clue, &template, &output).await
+            }
+            _ => recipe_export(origin, &clue, format, resolve).await,
+        },
+        RecipeSubCommands::Validate { clue, clues_file } => {
+            let clues = clues_from(clue, clues_file.as_deref())?;
+            for_each_clue(&clues, |clue| recipe_validate(origin, clue)).await
+        }
+        RecipeSubCommands::Diff { lhs, rhs } => recipe_diff(origin, &lhs, &rhs).await,
         RecipeSubCommands::Create {
             name,
             author,
             directions,
             information,
-        } => recipe_create(origin, &name, author.as_deref(), directions, information).await,
+            directions_stdin,
+            information_stdin,
+            image,
+            template,
+            template_tags,
+            fields,
+            format,
+        } => {
+            let options = CreateOptions {
+                author: author.as_deref(),
+                image: image.as_deref(),
+                template: template.as_deref().map(|clue| (clue, template_tags)),
+                extra: fields.into_iter().collect(),
+                format,
+            };
+            recipe_create(
+                origin,
+                &name,
+                TextInput::from_flags(directions, directions_stdin),
+                TextInput::from_flags(information, information_stdin),
+                &options,
+            )
+            .await
+        }
+        RecipeSubCommands::Favorite { clue } => recipe_tag(origin, &clue, favorite_label()).await,
+        RecipeSubCommands::Unfavorite { clue } => {
+            recipe_untag(origin, &clue, favorite_label()).await
+        }
+        RecipeSubCommands::Favorites { columns } => recipe_favorites(origin, &columns).await,
+        RecipeSubCommands::Snapshot { clue } => recipe_snapshot(origin, &clue).await,
+        RecipeSubCommands::Snapshots { clue } => recipe_snapshots(origin, &clue).await,
+        RecipeSubCommands::Duplicate {
+            clue,
+            name,
+            deep,
+            suffix,
+        } => duplicate_recipe(origin, &clue, name.as_deref(), &suffix, deep).await,
         RecipeSubCommands::Edit {
             clue,
             name,
             author,
             directions,
             information,
+            image,
+            clear,
+            fields,
         } => {
-            recipe_edit(
-                origin,
-                &clue,
-                name.as_deref(),
-                author.as_deref(),
-                directions,
-                information,
-            )
-            .await
+            let changes = RecipeChanges {
+                name: name.as_deref(),
+                author: author.as_deref(),
+                image: image.as_deref(),
+                extra: fields.into_iter().collect(),
+            };
+            recipe_edit(origin, &clue, changes, directions, information, &clear).await
+        }
+        RecipeSubCommands::Delete {
+            id,
+            matching,
+            yes,
+            dry_run,
+            cascade,
+            gc_ingredients,
+        } => {
+            let doomed = match (id.as_deref(), matching.as_deref()) {
+                (_, Some(pattern)) => ladle::recipe_index(origin, pattern).await?,
+                (Some(id), None) => vec![RecipeIndex {
+                    id: id.to_string(),
+                    name: id.to_string(),
+                }],
+                (None, None) => unreachable!(),
+            };
+            let ingredients = if gc_ingredients && !dry_run {
+                recipe_ingredient_ids(origin, &doomed).await?
+            } else {
+                HashSet::new()
+            };
+
+            match (id, matching) {
+                (_, Some(pattern)) => {
+                    recipe_delete_matching(origin, &pattern, yes, dry_run, cascade).await?
+                }
+                (Some(id), None) => recipe_delete(origin, &id).await?,
+                (None, None) => unreachable!(),
+            }
+
+            if gc_ingredients && !dry_run {
+                ingredient_collect(origin, &ingredients, yes).await?;
+            }
+            Ok(())
         }
-        RecipeSubCommands::Delete { id } => recipe_delete(origin, &id).await,
         RecipeSubCommands::Requirement { requirement } => {
             requirement_actions(origin, requirement).await
         }
@@ -325,175 +942,1598 @@ pub async fn actions(origin: &str, cmd: RecipeSubCommands) -> Result<(), Box<dyn
     }
 }
 
-async fn recipe_list(origin: &str, pattern: Option<&str>) -> Result<(), Box<dyn error::Error>> {
-    let mut recipes = ladle::recipe_index(origin, pattern.unwrap_or("")).await?;
-    recipes.sort_by(|lhs, rhs| unidecode(&lhs.name).cmp(&unidecode(&rhs.name)));
+/// Fetch the given recipes and keep those whose author matches `author`, ignoring case and
+/// accents
+async fn filter_by_author(
+    origin: &str,
+    recipes: Vec<RecipeIndex>,
+    author: &str,
+    all: bool,
+) -> Result<Vec<RecipeIndex>, Box<dyn error::Error>> {
+    check_fetch_count(recipes.len(), all, false)?;
 
-    let name_field_width = recipes
-        .iter()
-        .map(|r| unidecode(&r.name).len())
-        .max()
-        .unwrap_or(10);
+    let author = normalize_name(author);
+    let bar = progress_bar(recipes.len() as u64, "Fetching recipes");
 
-    let mut term = console::Term::buffered_stdout();
+    let fetches = recipes
+        .iter()
+        .map(|index| ladle::recipe_get(origin, &index.id));
 
-    for index in recipes.iter() {
-        write!(
-            term,
-            "{}    {}\n",
-            console::pad_str(
-                &index.name,
-                name_field_width,
-                console::Alignment::Left,
-                None
-            ),
-            index.id
-        )?;
+    let mut filtered = vec![];
+    for (index, fetch) in recipes.iter().zip(fetches) {
+        bar.inc(1);
+        if normalize_name(&fetch.await?.author) == author {
+            filtered.push(index.to_owned());
+        }
     }
 
-    term.flush()?;
-    Ok(())
+    bar.finish_and_clear();
+    Ok(filtered)
 }
 
-async fn recipe_show(origin: &str, recipe_clue: &str) -> Result<(), Box<dyn error::Error>> {
-    let recipe_index = recipe_identify(origin, recipe_clue).await?;
-    let recipe_tree = ladle::recipe_tree(origin, &recipe_index.id).await?;
-    let recipe = &recipe_tree[0];
+/// Selection and order of the recipes of a listing
+struct ListOptions<'a> {
+    author: Option<&'a str>,
+    all: bool,
+    sort: RecipeSort,
+    reverse: bool,
+    group_by_tag: bool,
+    exact: ExactMatch,
+}
 
-    let mut term = console::Term::buffered_stdout();
+/// Sort recipes by their number of requirements and dependencies, most complex first. Recipes of
+/// the same complexity keep their relative order
+async fn sort_by_complexity(
+    origin: &str,
+    recipes: Vec<RecipeIndex>,
+    all: bool,
+) -> Result<Vec<RecipeIndex>, Box<dyn error::Error>> {
+    check_fetch_count(recipes.len(), all, false)?;
+
+    let bar = progress_bar(recipes.len() as u64, "Fetching recipes");
+
+    let mut complexities = vec![];
+    for index in recipes.into_iter() {
+        bar.inc(1);
+        let recipe = ladle::recipe_get(origin, &index.id).await?;
+        complexities.push((recipe.requirements.len() + recipe.dependencies.len(), index));
+    }
 
-    write!(
-        term,
-        "{} by {}\n",
-        console::style(&recipe.name).bold(),
-        recipe.author
-    )?;
+    bar.finish_and_clear();
 
-    let terms = display_classifications(&recipe.classifications)?;
-    if terms.len() > 0 {
-        write!(
-            term,
-            "Contient: {}.\n",
-            console::style(terms.join(", ")).italic()
-        )?;
+    complexities.sort_by_key(|(complexity, _)| std::cmp::Reverse(*complexity));
+    Ok(complexities.into_iter().map(|(_, index)| index).collect())
+}
+
+async fn recipe_list(
+    origin: &str,
+    pattern: Option<&str>,
+    options: &ListOptions<'_>,
+    format: OutputFormat,
+    columns: &Columns,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut recipes = ladle::recipe_index(origin, pattern.unwrap_or("")).await?;
+    options.exact.retain(&mut recipes, |r| &r.name, pattern);
+    if let Some(author) = options.author {
+        recipes = filter_by_author(origin, recipes, author, options.all).await?;
+    }
+    recipes.sort_by(|lhs, rhs| unidecode(&lhs.name).cmp(&unidecode(&rhs.name)));
+    if let RecipeSort::Complexity = options.sort {
+        recipes = sort_by_complexity(origin, recipes, options.all).await?;
+    }
+    if options.reverse {
+        recipes.reverse();
     }
-    write!(term, "\n")?;
 
-    write!(term, "{}\n\n", console::style("Ingrédients").bold())?;
-    for recipe in recipe_tree.iter().rev() {
-        write!(term, "{}:\n", console::style(&recipe.name).underlined())?;
-        for req in recipe.requirements.iter() {
-            write!(term, "  ")?;
-            if req.optional {
-                write!(
-                    term,
-                    " - {}, {} (optionnel)\n",
-                    req.ingredient.name, req.quantity
-                )?;
-            } else {
-                write!(term, " - {}, {}\n", req.ingredient.name, req.quantity)?;
-            }
+    if let OutputFormat::Csv = format {
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        for index in recipes.iter() {
+            writer.serialize(index)?;
         }
-        write!(term, "\n")?;
+        writer.flush()?;
+        return Ok(());
     }
 
-    write!(term, "{}\n", console::style("Instructions").bold())?;
-    for recipe in recipe_tree.iter().rev() {
-        write!(
-            term,
-            "\n{}: {}\n",
-            console::style(&recipe.name).underlined(),
-            recipe.directions
-        )?;
+    if options.group_by_tag {
+        return write_grouped_by_tag(origin, &recipes, options.all, columns).await;
     }
 
-    let tags = recipe
+    let rows = recipes
+        .iter()
+        .map(|r| (r.name.as_str(), r.id.as_str()))
+        .collect::<Vec<_>>();
+    let mut term = console::Term::buffered_stdout();
+    columns.write_rows(&mut term, "    ", &rows)?;
+    term.flush()?;
+    Ok(())
+}
+
+/// Write recipes under a heading for each of their tags, in alphabetical order of the labels.
+/// Untagged recipes are listed last. The listing order of `recipes` is kept within each group
+async fn write_grouped_by_tag(
+    origin: &str,
+    recipes: &[RecipeIndex],
+    all: bool,
+    columns: &Columns,
+) -> Result<(), Box<dyn error::Error>> {
+    check_fetch_count(recipes.len(), all, false)?;
+
+    let bar = progress_bar(recipes.len() as u64, "Fetching recipes");
+    let mut groups: HashMap<String, Vec<&RecipeIndex>> = HashMap::new();
+    let mut untagged = vec![];
+    for index in recipes.iter() {
+        bar.inc(1);
+        let recipe = ladle::recipe_get(origin, &index.id).await?;
+        if recipe.tags.is_empty() {
+            untagged.push(index);
+        }
+        for tag in recipe.tags.iter() {
+            groups.entry(tag.name.clone()).or_default().push(index);
+        }
+    }
+    bar.finish_and_clear();
+
+    let mut labels = groups.keys().cloned().collect::<Vec<_>>();
+    labels.sort_by_key(|label| unidecode(label));
+
+    let mut term = console::Term::buffered_stdout();
+    let headings = labels
+        .iter()
+        .map(|label| (label.as_str(), &groups[label]))
+        .chain((!untagged.is_empty()).then_some(("(untagged)", &untagged)));
+    for (heading, members) in headings {
+        writeln!(term, "{}", console::style(heading).bold())?;
+        let rows = members
+            .iter()
+            .map(|r| (r.name.as_str(), r.id.as_str()))
+            .collect::<Vec<_>>();
+        columns.write_rows(&mut term, "    ", &rows)?;
+    }
+    term.flush()?;
+    Ok(())
+}
+
+/// Write the name and id of `recipes`, sorted by name
+fn write_recipes<'a>(
+    recipes: impl Iterator<Item = &'a ladle::models::Recipe>,
+    columns: &Columns,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut rows = recipes
+        .map(|r| (r.name.as_str(), r.id.as_str()))
+        .collect::<Vec<_>>();
+    rows.sort_by_key(|(name, _)| unidecode(name));
+
+    let mut term = console::Term::buffered_stdout();
+    columns.write_rows(&mut term, "    ", &rows)?;
+    term.flush()?;
+    Ok(())
+}
+
+/// List the recipes no other recipe depends on
+async fn recipe_roots(origin: &str, columns: &Columns) -> Result<(), Box<dyn error::Error>> {
+    let recipes = fetch_recipes(origin).await?;
+
+    let required = recipes
+        .iter()
+        .flat_map(|r| r.dependencies.iter().map(|d| d.recipe.id.as_str()))
+        .collect::<HashSet<_>>();
+
+    write_recipes(
+        recipes.iter().filter(|r| !required.contains(r.id.as_str())),
+        columns,
+    )
+}
+
+/// List the recipes without dependencies
+async fn recipe_leaves(origin: &str, columns: &Columns) -> Result<(), Box<dyn error::Error>> {
+    let recipes = fetch_recipes(origin).await?;
+
+    write_recipes(
+        recipes.iter().filter(|r| r.dependencies.is_empty()),
+        columns,
+    )
+}
+
+/// Clear the terminal and list recipes every `interval` seconds, until interrupted
+async fn recipe_watch(
+    origin: &str,
+    pattern: Option<&str>,
+    options: &ListOptions<'_>,
+    format: OutputFormat,
+    columns: &Columns,
+    interval: u64,
+) -> Result<(), Box<dyn error::Error>> {
+    let term = console::Term::stdout();
+    let mut interrupt = Box::pin(tokio::signal::ctrl_c());
+
+    loop {
+        term.clear_screen()?;
+        ladle::cache_clear();
+
+        tokio::select! {
+            listed = recipe_list(origin, pattern, options, format, columns) => listed?,
+            _ = &mut interrupt => return Ok(()),
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(interval)) => (),
+            _ = &mut interrupt => return Ok(()),
+        }
+    }
+}
+
+async fn recipe_show_brief(origin: &str, recipe_clue: &str) -> Result<(), Box<dyn error::Error>> {
+    let recipe_index = recipe_identify(origin, recipe_clue).await?;
+    let recipe = ladle::recipe_get(origin, &recipe_index.id).await?;
+
+    let tags = recipe
+        .tags
+        .iter()
+        .map(|t| format!("#{}", t.name))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    println!(
+        "{}\t{}\t{}\t{}",
+        recipe.name,
+        recipe.author,
+        recipe.requirements.len(),
+        tags
+    );
+
+    Ok(())
+}
+
+async fn recipe_show_raw(origin: &str, recipe_clue: &str) -> Result<(), Box<dyn error::Error>> {
+    let recipe = recipe_identify(origin, recipe_clue).await?;
+    println!("{}", ladle::recipe_get_raw(origin, &recipe.id).await?);
+    Ok(())
+}
+
+async fn recipe_show(origin: &str, recipe_clue: &str) -> Result<(), Box<dyn error::Error>> {
+    let recipe_index = recipe_identify(origin, recipe_clue).await?;
+    let recipe_tree = ladle::recipe_tree(origin, &recipe_index.id).await?;
+    let recipe = recipe_tree
+        .first()
+        .ok_or_else(|| ChopstickError(String::from("Recipe body was empty")))?;
+
+    let mut term = console::Term::buffered_stdout();
+
+    write!(
+        term,
+        "{} by {}\n",
+        console::style(&recipe.name).bold(),
+        recipe.author
+    )?;
+
+    if let Some(image) = recipe.image.as_deref().filter(|i| !i.is_empty()) {
+        writeln!(term, "Image: {}", image)?;
+    }
+
+    let terms = display_classifications(&recipe.classifications)?;
+    if terms.len() > 0 {
+        write!(
+            term,
+            "Contient: {}.\n",
+            console::style(terms.join(", ")).italic()
+        )?;
+    }
+    write!(term, "\n")?;
+
+    write!(term, "{}\n\n", console::style("Ingrédients").bold())?;
+    for recipe in recipe_tree.iter().rev() {
+        write!(term, "{}:\n", console::style(&recipe.name).underlined())?;
+        for req in recipe.requirements.iter() {
+            write!(term, "  ")?;
+            if req.optional {
+                write!(
+                    term,
+                    " - {}, {} (optionnel)\n",
+                    req.ingredient.name, req.quantity
+                )?;
+            } else {
+                write!(term, " - {}, {}\n", req.ingredient.name, req.quantity)?;
+            }
+        }
+        write!(term, "\n")?;
+    }
+
+    write!(term, "{}\n", console::style("Instructions").bold())?;
+    for recipe in recipe_tree.iter().rev() {
+        write!(
+            term,
+            "\n{}: {}\n",
+            console::style(&recipe.name).underlined(),
+            recipe.directions
+        )?;
+    }
+
+    let tags = recipe
         .tags
         .iter()
-        .map(|t| format!("#{}", t.name))
-        .collect::<Vec<_>>()
-        .join(" ");
-    write!(term, "\n{}\n", console::style(tags).italic())?;
+        .map(|t| format!("#{}", t.name))
+        .collect::<Vec<_>>()
+        .join(" ");
+    write!(term, "\n{}\n", console::style(tags).italic())?;
+
+    term.flush()?;
+    Ok(())
+}
+
+/// Multiply a quantity by `factor`. Quantities that cannot be parsed are returned untouched
+fn scale_quantity(quantity: &str, factor: f64) -> String {
+    match quantity.parse::<Quantity>() {
+        Ok(parsed) if factor != 1.0 => Quantity {
+            amount: parsed.amount * factor,
+            ..parsed
+        }
+        .to_string(),
+        _ => quantity.to_string(),
+    }
+}
+
+/// Sum the scaled quantities of requirements on the same ingredient, in order of first appearance.
+/// Quantities are summed when their units convert to the same canonical unit; quantities that
+/// cannot be parsed are kept as is. A sum is optional if all its requirements are
+fn sum_requirements<'a>(
+    requirements: impl Iterator<Item = &'a ladle::models::Requirement>,
+    factor: f64,
+) -> Vec<(&'a str, String, bool)> {
+    let mut sums: Vec<(&str, Result<Quantity, String>, bool)> = vec![];
+
+    for req in requirements {
+        let name = req.ingredient.name.as_str();
+        let quantity = match req.quantity.parse::<Quantity>() {
+            Ok(parsed) => Ok(Quantity {
+                amount: parsed.amount * factor,
+                ..parsed
+            }
+            .canonical()),
+            Err(_) => Err(req.quantity.clone()),
+        };
+
+        let existing = sums.iter_mut().find(|(n, q, _)| {
+            *n == name && matches!((q, &quantity), (Ok(lhs), Ok(rhs)) if lhs.unit == rhs.unit)
+        });
+
+        match (existing, quantity) {
+            (Some((_, Ok(sum), optional)), Ok(quantity)) => {
+                sum.amount += quantity.amount;
+                *optional &= req.optional;
+            }
+            (_, quantity) => sums.push((name, quantity, req.optional)),
+        }
+    }
+
+    sums.into_iter()
+        .map(|(name, quantity, optional)| {
+            let quantity = match quantity {
+                Ok(quantity) => quantity.humanize().to_string(),
+                Err(raw) => raw,
+            };
+            (name, quantity, optional)
+        })
+        .collect()
+}
+
+async fn recipe_ingredients(
+    origin: &str,
+    recipe_clue: &str,
+    deep: bool,
+    scale: f64,
+    normalize: bool,
+    optional: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let recipe_index = recipe_identify(origin, recipe_clue).await?;
+    let recipes = if deep {
+        ladle::recipe_tree(origin, &recipe_index.id).await?
+    } else {
+        vec![ladle::recipe_get(origin, &recipe_index.id).await?]
+    };
+
+    let requirements = recipes
+        .iter()
+        .rev()
+        .flat_map(|r| r.requirements.iter())
+        .filter(|req| optional || !req.optional);
+
+    let mut term = console::Term::buffered_stdout();
+
+    if normalize {
+        for (name, quantity, optional) in sum_requirements(requirements, scale) {
+            write!(term, "{}, {}", name, quantity)?;
+            if optional {
+                write!(term, " (optionnel)")?;
+            }
+            writeln!(term)?;
+        }
+
+        term.flush()?;
+        return Ok(());
+    }
+
+    for req in requirements {
+        write!(
+            term,
+            "{}, {}",
+            req.ingredient.name,
+            scale_quantity(&req.quantity, scale)
+        )?;
+        if req.optional {
+            write!(term, " (optionnel)")?;
+        }
+        writeln!(term)?;
+    }
+
+    term.flush()?;
+    Ok(())
+}
+
+/// Write a title underlined with `underline` to the width of the title
+fn print_header(
+    term: &mut console::Term,
+    title: &str,
+    underline: char,
+) -> Result<(), Box<dyn error::Error>> {
+    writeln!(term, "{}", title)?;
+    writeln!(
+        term,
+        "{}\n",
+        underline.to_string().repeat(title.chars().count())
+    )?;
+    Ok(())
+}
+
+async fn recipe_print(
+    origin: &str,
+    recipe_clue: &str,
+    width: usize,
+    scale: f64,
+) -> Result<(), Box<dyn error::Error>> {
+    let recipe_index = recipe_identify(origin, recipe_clue).await?;
+    let recipe_tree = ladle::recipe_tree(origin, &recipe_index.id).await?;
+    let recipe = recipe_tree
+        .first()
+        .ok_or_else(|| ChopstickError(String::from("Recipe body was empty")))?;
+
+    let mut term = console::Term::buffered_stdout();
+
+    print_header(&mut term, &recipe.name.to_uppercase(), '=')?;
+    if !recipe.author.is_empty() {
+        writeln!(term, "par {}\n", recipe.author)?;
+    }
+
+    let terms = display_classifications(&recipe.classifications)?;
+    if !terms.is_empty() {
+        for line in wrap(&format!("Contient: {}.", terms.join(", ")), width) {
+            writeln!(term, "{}", line)?;
+        }
+        writeln!(term)?;
+    }
+
+    writeln!(term)?;
+    print_header(&mut term, "INGRÉDIENTS", '-')?;
+    for recipe in recipe_tree.iter().rev() {
+        writeln!(term, "{}\n", recipe.name)?;
+        for req in recipe.requirements.iter() {
+            let mut item = format!(
+                "{}, {}",
+                req.ingredient.name,
+                scale_quantity(&req.quantity, scale)
+            );
+            if req.optional {
+                item.push_str(" (optionnel)");
+            }
+            for (index, line) in wrap(&item, width.saturating_sub(6)).iter().enumerate() {
+                let bullet = if index == 0 { "[ ]" } else { "   " };
+                writeln!(term, "  {} {}", bullet, line)?;
+            }
+        }
+        writeln!(term)?;
+    }
+
+    writeln!(term)?;
+    print_header(&mut term, "INSTRUCTIONS", '-')?;
+    for recipe in recipe_tree.iter().rev() {
+        writeln!(term, "{}\n", recipe.name)?;
+        let steps = recipe
+            .directions
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty());
+        for (number, step) in steps.enumerate() {
+            let label = format!("{}.", number + 1);
+            let indent = label.len() + 3;
+            for (index, line) in wrap(step, width.saturating_sub(indent)).iter().enumerate() {
+                if index == 0 {
+                    writeln!(term, "  {} {}", label, line)?;
+                } else {
+                    writeln!(term, "{}{}", " ".repeat(indent), line)?;
+                }
+            }
+            writeln!(term)?;
+        }
+        writeln!(term)?;
+    }
+
+    term.flush()?;
+    Ok(())
+}
+
+/// Load the price table, defaulting to `~/.config/chopstick-prices.toml`. Prices are given per
+/// canonical unit of the quantities, as in the price of a gram or a millilitre, or per piece for
+/// quantities without a unit
+fn load_prices(path: Option<&Path>) -> Result<HashMap<String, f64>, Box<dyn error::Error>> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let mut home = dirs::home_dir()
+                .ok_or_else(|| ChopstickError(String::from("Failed to locate home directory")))?;
+            home.push(".config");
+            home.push("chopstick-prices");
+            home.set_extension("toml");
+            home
+        }
+    };
+
+    Ok(toml::from_str(&fs::read_to_string(path)?)?)
+}
+
+async fn recipe_cost(
+    origin: &str,
+    recipe_clue: &str,
+    prices: Option<&Path>,
+) -> Result<(), Box<dyn error::Error>> {
+    let prices = load_prices(prices)?;
+    let recipe_index = recipe_identify(origin, recipe_clue).await?;
+    let recipe_tree = ladle::recipe_tree(origin, &recipe_index.id).await?;
+
+    let mut total = 0.0;
+    let mut unknown = vec![];
+
+    let mut term = console::Term::buffered_stdout();
+
+    for recipe in recipe_tree.iter().rev() {
+        for req in recipe.requirements.iter() {
+            let quantity = req
+                .quantity
+                .parse::<Quantity>()
+                .map(|q| q.canonical())
+                .ok()
+                .filter(|q| matches!(q.unit.as_str(), "" | "g" | "ml"));
+
+            match (prices.get(&req.ingredient.name), quantity) {
+                (Some(price), Some(quantity)) => {
+                    let cost = price * quantity.amount;
+                    total += cost;
+                    writeln!(
+                        term,
+                        " - {}, {}: {:.2}",
+                        req.ingredient.name, req.quantity, cost
+                    )?;
+                }
+                _ => {
+                    writeln!(
+                        term,
+                        " - {}, {}: unknown",
+                        req.ingredient.name, req.quantity
+                    )?;
+                    unknown.push(req.ingredient.name.as_str());
+                }
+            }
+        }
+    }
+
+    writeln!(term, "\n{}: {:.2}", console::style("Total").bold(), total)?;
+    if !unknown.is_empty() {
+        writeln!(
+            term,
+            "{}: {}",
+            console::style("Unknown").italic(),
+            unknown.join(", ")
+        )?;
+    }
+
+    term.flush()?;
+    Ok(())
+}
+
+/// Maximum number of ingredients fetched at once when resolving an export
+const EXPORT_CONCURRENCY: usize = 8;
+
+async fn recipe_export(
+    origin: &str,
+    recipe_clue: &str,
+    format: ExportFormat,
+    resolve: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let recipe_index = recipe_identify(origin, recipe_clue).await?;
+    let recipe = ladle::recipe_get(origin, &recipe_index.id).await?;
+    let mut document = serde_json::to_value(&recipe)?;
+
+    if resolve {
+        let ingredients = stream::iter(
+            recipe
+                .requirements
+                .iter()
+                .map(|req| ladle::ingredient_get(origin, &req.ingredient.id)),
+        )
+        .buffered(EXPORT_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+        let requirements = document["requirements"]
+            .as_array_mut()
+            .ok_or_else(|| ChopstickError(String::from("Recipe has no requirement list")))?;
+        for (requirement, ingredient) in requirements.iter_mut().zip(ingredients) {
+            let mut ingredient = ingredient?;
+            ingredient.used_in.clear();
+            requirement["ingredient"] = serde_json::to_value(ingredient)?;
+        }
+    }
+
+    match format {
+        ExportFormat::Json => println!("{}", serde_json::to_string_pretty(&document)?),
+        ExportFormat::Pdf => {
+            return Err(Box::new(ChopstickError(String::from(
+                "PDF exports require a template and an output file",
+            ))))
+        }
+    }
+
+    Ok(())
+}
+
+/// Data passed to the templates of PDF exports. Ingredients and directions are grouped by recipe,
+/// dependencies first, as in `recipe show`
+#[derive(Serialize)]
+struct PrintableRecipe<'a> {
+    name: &'a str,
+    author: &'a str,
+    ingredients: Vec<PrintableGroup<'a>>,
+    steps: Vec<PrintableGroup<'a>>,
+    tags: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct PrintableGroup<'a> {
+    recipe: &'a str,
+    items: Vec<String>,
+}
+
+async fn recipe_export_pdf(
+    origin: &str,
+    recipe_clue: &str,
+    template: &Path,
+    output: &Path,
+) -> Result<(), Box<dyn error::Error>> {
+    let recipe_index = recipe_identify(origin, recipe_clue).await?;
+    let recipe_tree = ladle::recipe_tree(origin, &recipe_index.id).await?;
+    let recipe = recipe_tree
+        .first()
+        .ok_or_else(|| ChopstickError(String::from("Recipe body was empty")))?;
+
+    let data = PrintableRecipe {
+        name: &recipe.name,
+        author: &recipe.author,
+        ingredients: recipe_tree
+            .iter()
+            .rev()
+            .map(|r| PrintableGroup {
+                recipe: &r.name,
+                items: r
+                    .requirements
+                    .iter()
+                    .map(|req| format!("{}, {}", req.ingredient.name, req.quantity))
+                    .collect(),
+            })
+            .collect(),
+        steps: recipe_tree
+            .iter()
+            .rev()
+            .map(|r| PrintableGroup {
+                recipe: &r.name,
+                items: r
+                    .directions
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(String::from)
+                    .collect(),
+            })
+            .collect(),
+        tags: recipe.tags.iter().map(|t| t.name.as_str()).collect(),
+    };
+
+    let status = std::process::Command::new("typst")
+        .arg("compile")
+        .arg("--input")
+        .arg(format!("recipe={}", serde_json::to_string(&data)?))
+        .arg(template)
+        .arg(output)
+        .status()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ChopstickError(String::from(
+                "PDF exports require the `typst` compiler, which was not found in PATH",
+            )),
+            _ => ChopstickError(format!("Failed to run `typst`: {}", e)),
+        })?;
+
+    if !status.success() {
+        return Err(Box::new(ChopstickError(format!(
+            "`typst` failed to compile {} ({})",
+            template.display(),
+            status
+        ))));
+    }
+
+    log::info!("Exported `{}` to {}", recipe.name, output.display());
+    Ok(())
+}
+
+/// Follow the dependencies of `id` in the tree, returning the first cycle found as a list of
+/// recipe ids
+fn find_cycle(
+    tree: &HashMap<&str, &ladle::models::Recipe>,
+    id: &str,
+    path: &mut Vec<String>,
+    visited: &mut HashSet<String>,
+) -> Option<Vec<String>> {
+    if let Some(start) = path.iter().position(|p| p == id) {
+        return Some(path[start..].to_vec());
+    }
+
+    if !visited.insert(id.to_string()) {
+        return None;
+    }
+
+    path.push(id.to_string());
+    if let Some(recipe) = tree.get(id) {
+        for dependency in recipe.dependencies.iter() {
+            if let Some(cycle) = find_cycle(tree, &dependency.recipe.id, path, visited) {
+                return Some(cycle);
+            }
+        }
+    }
+    path.pop();
+
+    None
+}
+
+async fn recipe_validate(origin: &str, recipe_clue: &str) -> Result<(), Box<dyn error::Error>> {
+    let recipe_index = recipe_identify(origin, recipe_clue).await?;
+    let recipe_tree = ladle::recipe_tree(origin, &recipe_index.id).await?;
+    let recipe = recipe_tree
+        .first()
+        .ok_or_else(|| ChopstickError(String::from("Recipe body was empty")))?;
+
+    let mut problems = vec![];
+
+    if recipe.directions.trim().is_empty() {
+        problems.push(String::from("Directions are empty"));
+    }
+
+    for req in recipe.requirements.iter() {
+        if req.quantity.trim().is_empty() {
+            problems.push(format!(
+                "Requirement of `{}` has an empty quantity",
+                req.ingredient.name
+            ));
+        }
+    }
+
+    let resolved = join_all(
+        recipe
+            .requirements
+            .iter()
+            .map(|req| ladle::ingredient_get(origin, &req.ingredient.id)),
+    )
+    .await;
+    for (req, ingredient) in recipe.requirements.iter().zip(resolved) {
+        if let Err(message) = ingredient {
+            problems.push(format!(
+                "Ingredient `{}` ({}) failed to resolve: {}",
+                req.ingredient.name, req.ingredient.id, message
+            ));
+        }
+    }
+
+    for ingredient in recipe.duplicate_requirement_ingredients() {
+        let quantities = recipe
+            .requirements
+            .iter()
+            .filter(|req| req.ingredient.id == ingredient.id)
+            .map(|req| req.quantity.as_str())
+            .collect::<Vec<_>>();
+        problems.push(format!(
+            "Ingredient `{}` is required more than once ({})",
+            ingredient.name,
+            quantities.join(", ")
+        ));
+    }
+
+    let tree = recipe_tree
+        .iter()
+        .map(|r| (r.id.as_str(), r))
+        .collect::<HashMap<_, _>>();
+    if let Some(cycle) = find_cycle(&tree, &recipe.id, &mut vec![], &mut HashSet::new()) {
+        let names = cycle
+            .iter()
+            .chain(cycle.first())
+            .map(|id| {
+                tree.get(id.as_str())
+                    .map_or(id.as_str(), |r| r.name.as_str())
+            })
+            .collect::<Vec<_>>();
+        problems.push(format!("Dependency cycle: {}", names.join(" -> ")));
+    }
+
+    let mut term = console::Term::buffered_stdout();
+    for problem in problems.iter() {
+        writeln!(term, " - {}", problem)?;
+    }
+    term.flush()?;
+
+    if problems.is_empty() {
+        log::info!("Recipe `{}` is valid", recipe.name);
+        Ok(())
+    } else {
+        Err(Box::new(ChopstickError(format!(
+            "Validation of `{}` failed: {} problems",
+            recipe.name,
+            problems.len()
+        ))))
+    }
+}
+
+/// A line of a diff between two texts
+enum LineChange<'a> {
+    Kept(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Line diff of `old` and `new`, from their longest common subsequence of lines
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<LineChange<'a>> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = vec![];
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            changes.push(LineChange::Kept(old[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            changes.push(LineChange::Removed(old[i]));
+            i += 1;
+        } else {
+            changes.push(LineChange::Added(new[j]));
+            j += 1;
+        }
+    }
+    changes.extend(old[i..].iter().map(|line| LineChange::Removed(line)));
+    changes.extend(new[j..].iter().map(|line| LineChange::Added(line)));
+
+    changes
+}
+
+/// Write a change marked with `-` or `+`. `console` only colors it when writing to a terminal
+fn write_change(
+    term: &mut console::Term,
+    change: &LineChange<'_>,
+) -> Result<(), Box<dyn error::Error>> {
+    match change {
+        LineChange::Kept(line) => writeln!(term, "  {}", line)?,
+        LineChange::Removed(line) => {
+            writeln!(term, "{}", console::style(format!("- {}", line)).red())?
+        }
+        LineChange::Added(line) => {
+            writeln!(term, "{}", console::style(format!("+ {}", line)).green())?
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the differences between two sets of displayed items, under a section title
+fn write_set_diff(
+    term: &mut console::Term,
+    title: &str,
+    old: &BTreeSet<String>,
+    new: &BTreeSet<String>,
+) -> Result<(), Box<dyn error::Error>> {
+    if old == new {
+        return Ok(());
+    }
+
+    writeln!(term, "{}", console::style(title).bold())?;
+    for removed in old.difference(new) {
+        write_change(term, &LineChange::Removed(removed))?;
+    }
+    for added in new.difference(old) {
+        write_change(term, &LineChange::Added(added))?;
+    }
+    writeln!(term)?;
+
+    Ok(())
+}
+
+/// Write the line diff of a text field, under a section title
+fn write_text_diff(
+    term: &mut console::Term,
+    title: &str,
+    old: &str,
+    new: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    if old == new {
+        return Ok(());
+    }
+
+    writeln!(term, "{}", console::style(title).bold())?;
+    for change in diff_lines(old, new).iter() {
+        write_change(term, change)?;
+    }
+    writeln!(term)?;
+
+    Ok(())
+}
+
+/// Label marking favorite recipes, unless set with `favorite_label`
+const FAVORITE_LABEL: &str = "favorite";
+
+static CONFIGURED_FAVORITE_LABEL: OnceLock<String> = OnceLock::new();
+
+pub fn set_favorite_label(label: String) {
+    CONFIGURED_FAVORITE_LABEL.get_or_init(|| label);
+}
+
+fn favorite_label() -> &'static str {
+    CONFIGURED_FAVORITE_LABEL
+        .get()
+        .map(String::as_str)
+        .unwrap_or(FAVORITE_LABEL)
+}
+
+async fn recipe_favorites(origin: &str, columns: &Columns) -> Result<(), Box<dyn error::Error>> {
+    let labels = ladle::label_index(origin, favorite_label()).await?;
+    let favorites = match find_named(&labels, |l| &l.name, favorite_label()) {
+        Some(label) => ladle::label_get(origin, &label.id).await?.tagged_recipes,
+        None => BTreeSet::new(),
+    };
+
+    let mut rows = favorites
+        .iter()
+        .map(|r| (r.name.as_str(), r.id.as_str()))
+        .collect::<Vec<_>>();
+    rows.sort_by_key(|(name, _)| unidecode(name));
+
+    let mut term = console::Term::buffered_stdout();
+    columns.write_rows(&mut term, "    ", &rows)?;
+    term.flush()?;
+    Ok(())
+}
+
+static SNAPSHOT_DIRECTORY: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_snapshot_directory(path: PathBuf) {
+    SNAPSHOT_DIRECTORY.get_or_init(|| path);
+}
+
+/// Directory holding the snapshots of a recipe, under the `snapshot_directory` setting or
+/// `chopstick/snapshots` in the user's data directory
+fn snapshot_directory(id: &str) -> Result<PathBuf, Box<dyn error::Error>> {
+    let mut path = match SNAPSHOT_DIRECTORY.get() {
+        Some(path) => path.clone(),
+        None => {
+            let mut data = dirs::data_dir()
+                .ok_or_else(|| ChopstickError(String::from("Failed to locate data directory")))?;
+            data.push("chopstick");
+            data.push("snapshots");
+            data
+        }
+    };
+    path.push(id);
+    Ok(path)
+}
+
+async fn recipe_snapshot(origin: &str, clue: &str) -> Result<(), Box<dyn error::Error>> {
+    let recipe = recipe_identify(origin, clue).await?;
+    let tree = ladle::recipe_tree(origin, &recipe.id).await?;
+
+    let mut path = snapshot_directory(&recipe.id)?;
+    fs::create_dir_all(&path)?;
+    path.push(timestamp());
+    path.set_extension("json");
+
+    fs::write(&path, serde_json::to_string_pretty(&tree)?)?;
+    log::info!("Saved `{}` to {}", recipe.name, path.display());
+    Ok(())
+}
+
+async fn recipe_snapshots(origin: &str, clue: &str) -> Result<(), Box<dyn error::Error>> {
+    let recipe = recipe_identify(origin, clue).await?;
+    let directory = snapshot_directory(&recipe.id)?;
+
+    let mut snapshots = match fs::read_dir(&directory) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == "json"))
+            .collect::<Vec<_>>(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(Box::new(e)),
+    };
+    snapshots.sort();
+
+    let mut term = console::Term::buffered_stdout();
+    writeln!(
+        term,
+        "{} ({} snapshots)",
+        console::style(&recipe.name).bold(),
+        snapshots.len()
+    )?;
+    for path in snapshots.iter() {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        writeln!(term, "  - {} ({})", stem, path.display())?;
+    }
+    term.flush()?;
+    Ok(())
+}
+
+async fn recipe_diff(
+    origin: &str,
+    lhs_clue: &str,
+    rhs_clue: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    let lhs = recipe_identify(origin, lhs_clue).await?;
+    let rhs = recipe_identify(origin, rhs_clue).await?;
+    let lhs = ladle::recipe_get(origin, &lhs.id).await?;
+    let rhs = ladle::recipe_get(origin, &rhs.id).await?;
+
+    let requirements = |recipe: &ladle::models::Recipe| {
+        recipe
+            .requirements
+            .iter()
+            .map(|req| {
+                let optional = if req.optional { " (optionnel)" } else { "" };
+                format!("{}, {}{}", req.ingredient.name, req.quantity, optional)
+            })
+            .collect::<BTreeSet<_>>()
+    };
+    let dependencies = |recipe: &ladle::models::Recipe| {
+        recipe
+            .dependencies
+            .iter()
+            .map(|dep| {
+                let optional = if dep.optional { " (optionnel)" } else { "" };
+                format!("{}, {}{}", dep.recipe.name, dep.quantity, optional)
+            })
+            .collect::<BTreeSet<_>>()
+    };
+    let tags = |recipe: &ladle::models::Recipe| {
+        recipe
+            .tags
+            .iter()
+            .map(|t| format!("#{}", t.name))
+            .collect::<BTreeSet<_>>()
+    };
+
+    let mut term = console::Term::buffered_stdout();
+
+    write_text_diff(&mut term, "Name", &lhs.name, &rhs.name)?;
+    write_text_diff(&mut term, "Author", &lhs.author, &rhs.author)?;
+    write_set_diff(
+        &mut term,
+        "Requirements",
+        &requirements(&lhs),
+        &requirements(&rhs),
+    )?;
+    write_set_diff(
+        &mut term,
+        "Dependencies",
+        &dependencies(&lhs),
+        &dependencies(&rhs),
+    )?;
+    write_set_diff(&mut term, "Tags", &tags(&lhs), &tags(&rhs))?;
+    write_text_diff(&mut term, "Directions", &lhs.directions, &rhs.directions)?;
+    write_text_diff(&mut term, "Information", &lhs.information, &rhs.information)?;
 
     term.flush()?;
     Ok(())
 }
 
+/// Clues designated on the command line, either directly or from a file
+fn clues_from(
+    clue: Option<String>,
+    clues_file: Option<&Path>,
+) -> Result<Vec<String>, Box<dyn error::Error>> {
+    match clues_file {
+        Some(path) => read_clues(path),
+        None => Ok(clue.into_iter().collect()),
+    }
+}
+
+/// Author used on recipe creation when none is given, set from the configuration file
+static DEFAULT_AUTHOR: OnceLock<String> = OnceLock::new();
+
+pub fn set_default_author(author: String) {
+    DEFAULT_AUTHOR.get_or_init(|| author);
+}
+
+/// Quantity of a dependency created without one, meaning the whole sub-recipe is used
+const DEPENDENCY_QUANTITY: &str = "1 batch";
+
+static DEFAULT_DEPENDENCY_QUANTITY: OnceLock<String> = OnceLock::new();
+
+pub fn set_default_dependency_quantity(quantity: String) {
+    DEFAULT_DEPENDENCY_QUANTITY.get_or_init(|| quantity);
+}
+
+/// Read standard input until EOF. Fails if it is a terminal instead of waiting for input
+fn read_stdin() -> Result<String, Box<dyn error::Error>> {
+    let mut stdin = std::io::stdin();
+
+    if stdin.is_terminal() {
+        return Err(Box::new(ChopstickError(String::from(
+            "Standard input is a terminal, pipe the text to read instead",
+        ))));
+    }
+
+    let mut text = String::new();
+    stdin.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Requirements and tags copied from a template recipe on recipe creation
+struct Template {
+    requirements: Vec<ladle::models::Requirement>,
+    tags: Vec<ladle::models::LabelIndex>,
+}
+
+impl Template {
+    async fn fetch(origin: &str, clue: &str, tags: bool) -> Result<Self, Box<dyn error::Error>> {
+        let recipe = recipe_identify(origin, clue).await?;
+
+        let requirements = ladle::recipe_get_requirements(origin, &recipe.id).await?;
+        let tags = if tags {
+            ladle::recipe_get(origin, &recipe.id)
+                .await?
+                .tags
+                .into_iter()
+                .collect()
+        } else {
+            vec![]
+        };
+
+        Ok(Template { requirements, tags })
+    }
+
+    /// Copy the template's contents to the recipe with id `recipe_id`. Returns the number of
+    /// failures, which are logged
+    async fn apply(&self, origin: &str, recipe_id: &str) -> usize {
+        let mut failures = 0;
+
+        for req in self.requirements.iter() {
+            let requirement = ladle::NewRequirement {
+                recipe_id,
+                ingredient_id: &req.ingredient.id,
+                quantity: &req.quantity,
+                optional: req.optional,
+            };
+            if let Err(message) = ladle::requirement_add(origin, requirement).await {
+                failures += 1;
+                log::error!(
+                    "Failed to copy requirement of `{}`: {}",
+                    req.ingredient.name,
+                    message
+                );
+            }
+        }
+
+        for tag in self.tags.iter() {
+            if let Err(message) = ladle::recipe_tag(origin, recipe_id, &tag.name).await {
+                failures += 1;
+                log::error!("Failed to copy tag `{}`: {}", tag.name, message);
+            }
+        }
+
+        failures
+    }
+}
+
+/// Number of times the creation of a recipe is attempted when its text was typed in an editor
+const CREATE_ATTEMPTS: usize = 3;
+
+/// Settings of a recipe creation, besides its name and text
+struct CreateOptions<'a> {
+    /// Author of the recipe, defaulting to the one of the configuration file
+    author: Option<&'a str>,
+
+    /// URL of a picture of the recipe
+    image: Option<&'a str>,
+
+    /// Recipe to copy the requirements of, and whether to copy its tags too
+    template: Option<(&'a str, bool)>,
+
+    /// Fields ladle does not model, sent along with the others
+    extra: Map<String, Value>,
+
+    /// How to report the created recipe on standard output
+    format: CreatedFormat,
+}
+
 async fn recipe_create(
     origin: &str,
     name: &str,
-    author: Option<&str>,
-    directions: bool,
-    information: bool,
+    directions: TextInput,
+    information: TextInput,
+    options: &CreateOptions<'_>,
 ) -> Result<(), Box<dyn error::Error>> {
-    let directions_str = if directions {
-        dialoguer::Editor::new()
-            .edit("Enter recipe directions")
-            .unwrap()
-    } else {
-        None
+    // Resolve the template first, to fail before creating anything
+    let template = match options.template {
+        Some((clue, tags)) => Some(Template::fetch(origin, clue, tags).await?),
+        None => None,
     };
 
-    let information_str = if information {
-        dialoguer::Editor::new()
-            .edit("Enter recipe information")
-            .unwrap()
-    } else {
-        None
+    let directions_str = directions.read("Enter recipe directions")?;
+    let information_str = information.read("Enter recipe information")?;
+
+    // Text typed in an editor cannot be read again, so it is not discarded on failure
+    let typed = [
+        (directions, &directions_str),
+        (information, &information_str),
+    ]
+    .into_iter()
+    .any(|(input, text)| matches!(input, TextInput::Editor) && !text.is_empty());
+
+    let mut attempts = 0;
+    let recipe = loop {
+        attempts += 1;
+        let error = match ladle::recipe_create_extra(
+            origin,
+            name,
+            options
+                .author
+                .or(DEFAULT_AUTHOR.get().map(String::as_str))
+                .unwrap_or(""),
+            &directions_str,
+            &information_str,
+            options.image,
+            &options.extra,
+        )
+        .await
+        {
+            Ok(recipe) => break recipe,
+            Err(error) => error,
+        };
+
+        if !typed {
+            return Err(error);
+        }
+
+        let retry = attempts < CREATE_ATTEMPTS && std::io::stdin().is_terminal() && {
+            log::error!("Failed to create recipe `{}`: {}", name, error);
+            dialoguer::Confirm::new()
+                .with_prompt("Retry creating the recipe ?")
+                .default(true)
+                .interact()?
+        };
+
+        if !retry {
+            eprintln!("Directions:\n{}\n", directions_str);
+            eprintln!("Information:\n{}", information_str);
+            return Err(error);
+        }
     };
 
-    ladle::recipe_create(
-        origin,
-        name,
-        author.unwrap_or(""),
-        &directions_str.unwrap_or(String::default()),
-        &information_str.unwrap_or(String::default()),
-    )
-    .await?;
+    print_created(options.format, &recipe.id, &recipe.name, &recipe)?;
+
+    if let Some(template) = template {
+        let failures = template.apply(origin, &recipe.id).await;
+        if failures > 0 {
+            return Err(Box::new(ChopstickError(format!(
+                "Created recipe `{}` but failed to copy {} template entries",
+                name, failures
+            ))));
+        }
+    }
+
     Ok(())
 }
 
+/// Replace a field's new value by an empty string if it is to be cleared
+fn cleared<'a>(
+    clear: &[ClearableField],
+    field: ClearableField,
+    value: Option<&'a str>,
+) -> Option<&'a str> {
+    if clear.contains(&field) {
+        Some("")
+    } else {
+        value
+    }
+}
+
+/// Undo the newline editors append to the last line of a file, unless the original text already
+/// ended with one
+fn normalize_edited(original: &str, mut edited: String) -> String {
+    if !original.ends_with('\n') {
+        if edited.ends_with("\r\n") {
+            edited.truncate(edited.len() - 2);
+        } else if edited.ends_with('\n') {
+            edited.truncate(edited.len() - 1);
+        }
+    }
+
+    edited
+}
+
+/// Open `text` in `editor`, reporting a failure to run the editor as an error instead of an I/O
+/// failure with no context
+fn launch_editor(
+    editor: &dialoguer::Editor,
+    text: &str,
+) -> Result<Option<String>, Box<dyn error::Error>> {
+    editor.edit(text).map_err(|e| {
+        Box::new(ChopstickError(format!("Failed to launch editor: {}", e))) as Box<dyn error::Error>
+    })
+}
+
+/// Open `original` in an editor. Returns `None` if the file was not saved, emptied or left
+/// unchanged, so that no-op edits do not update the field. Use `--clear` to empty a field
+fn edit_text(original: &str) -> Result<Option<String>, Box<dyn error::Error>> {
+    Ok(
+        launch_editor(dialoguer::Editor::new().trim_newlines(false), original)?
+            .map(|edited| normalize_edited(original, edited))
+            .filter(|edited| !edited.trim().is_empty() && edited != original),
+    )
+}
+
 async fn recipe_edit(
     origin: &str,
     recipe_clue: &str,
-    name: Option<&str>,
-    author: Option<&str>,
+    changes: RecipeChanges<'_>,
     directions: bool,
     information: bool,
+    clear: &[ClearableField],
 ) -> Result<(), Box<dyn error::Error>> {
+    let conflicting = clear.iter().find(|field| match field {
+        ClearableField::Author => changes.author.is_some(),
+        ClearableField::Directions => directions,
+        ClearableField::Information => information,
+        ClearableField::Image => changes.image.is_some(),
+    });
+    if let Some(field) = conflicting {
+        return Err(Box::new(ChopstickError(format!(
+            "Cannot both set and clear {:?}",
+            field
+        ))));
+    }
+
     let recipe = recipe_identify(origin, recipe_clue).await?;
     let old_recipe = ladle::recipe_get(origin, &recipe.id).await?;
 
     let directions_str = if directions {
-        dialoguer::Editor::new()
-            .edit(&old_recipe.directions)
-            .unwrap()
+        edit_text(&old_recipe.directions)?
     } else {
         None
     };
 
     let information_str = if information {
-        dialoguer::Editor::new()
-            .edit(&old_recipe.information)
-            .unwrap()
+        edit_text(&old_recipe.information)?
     } else {
         None
     };
 
-    ladle::recipe_update(
-        origin,
-        &recipe.id,
-        name,
-        author,
-        directions_str.as_deref(),
-        information_str.as_deref(),
-    )
-    .await?;
+    let update = ladle::RecipeUpdate {
+        name: changes.name,
+        author: cleared(clear, ClearableField::Author, changes.author),
+        directions: cleared(clear, ClearableField::Directions, directions_str.as_deref()),
+        information: cleared(
+            clear,
+            ClearableField::Information,
+            information_str.as_deref(),
+        ),
+        image: cleared(clear, ClearableField::Image, changes.image),
+    };
+    ladle::recipe_update_extra(origin, &recipe.id, update, &changes.extra).await?;
     Ok(())
 }
 
 async fn recipe_delete(origin: &str, id: &str) -> Result<(), Box<dyn error::Error>> {
-    ladle::recipe_delete(origin, id).await
+    ladle::recipe_delete(origin, id).await.map_err(|message| {
+        match message.downcast_ref::<ladle::NotFoundError>() {
+            Some(_) => Box::new(NotFoundError(format!("No recipe with id `{}`", id))),
+            None => message,
+        }
+    })
+}
+
+/// Ids of the ingredients required by `recipes`
+async fn recipe_ingredient_ids(
+    origin: &str,
+    recipes: &[RecipeIndex],
+) -> Result<HashSet<String>, Box<dyn error::Error>> {
+    let mut ids = HashSet::new();
+    for index in recipes.iter() {
+        let recipe = ladle::recipe_get(origin, &index.id).await?;
+        ids.extend(recipe.requirements.into_iter().map(|r| r.ingredient.id));
+    }
+    Ok(ids)
+}
+
+/// Delete the ingredients among `ids` no recipe uses anymore
+async fn ingredient_collect(
+    origin: &str,
+    ids: &HashSet<String>,
+    yes: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut orphans = vec![];
+    for id in ids.iter() {
+        let ingredient = ladle::ingredient_get(origin, id).await?;
+        if ingredient.used_in.is_empty() {
+            orphans.push(ingredient);
+        }
+    }
+
+    if orphans.is_empty() {
+        log::info!("No ingredient was left unused");
+        return Ok(());
+    }
+
+    orphans.sort_by_key(|i| unidecode(&i.name));
+    for ingredient in orphans.iter() {
+        println!("{}\t{}", ingredient.id, ingredient.name);
+    }
+
+    if !yes
+        && !dialoguer::Confirm::new()
+            .with_prompt(format!("Delete {} unused ingredients ?", orphans.len()))
+            .default(false)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    for ingredient in orphans.iter() {
+        match ladle::ingredient_delete(origin, &ingredient.id).await {
+            Ok(()) => log::info!(
+                "Deleted unused ingredient `{}` ({})",
+                ingredient.name,
+                ingredient.id
+            ),
+            Err(message) => {
+                failures += 1;
+                log::error!(
+                    "Failed to delete ingredient `{}`: {}",
+                    ingredient.name,
+                    message
+                )
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "Failed to delete {} unused ingredients",
+            failures
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Delete all recipes matching a pattern. Recipes other recipes depend on are only deleted with
+/// `cascade`, after removing the dependencies on them.
+async fn recipe_delete_matching(
+    origin: &str,
+    pattern: &str,
+    yes: bool,
+    dry_run: bool,
+    cascade: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let matches = ladle::recipe_index(origin, pattern).await?;
+
+    if matches.is_empty() {
+        log::info!("No recipes match `{}`", pattern);
+        return Ok(());
+    }
+
+    for recipe in matches.iter() {
+        println!("{}\t{}", recipe.id, recipe.name);
+    }
+
+    let doomed = matches
+        .iter()
+        .map(|r| r.id.as_str())
+        .collect::<HashSet<_>>();
+    let all_recipes = ladle::recipe_index(origin, "").await?;
+    let fetches = all_recipes
+        .iter()
+        .filter(|r| !doomed.contains(r.id.as_str()))
+        .map(|r| ladle::recipe_get(origin, &r.id));
+
+    let mut dependents = vec![];
+    let mut responses = stream::iter(fetches).buffered(ladle::bulk::CONCURRENCY);
+    while let Some(recipe) = responses.next().await {
+        let recipe = recipe?;
+        for dependency in recipe
+            .dependencies
+            .iter()
+            .filter(|d| doomed.contains(d.recipe.id.as_str()))
+        {
+            dependents.push((recipe.clone(), dependency.recipe.clone()));
+        }
+    }
+
+    if !dependents.is_empty() && !cascade {
+        return Err(Box::new(ChopstickError(format!(
+            "Matching recipes are required by other recipes: {}. Use --cascade to delete them anyway",
+            dependents
+                .iter()
+                .map(|(recipe, required)| format!("`{}` requires `{}`", recipe.name, required.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))));
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    if !yes
+        && !dialoguer::Confirm::new()
+            .with_prompt(format!("Delete {} recipes ?", matches.len()))
+            .default(false)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    for (recipe, required) in dependents.iter() {
+        match ladle::dependency_delete(origin, &recipe.id, &required.id).await {
+            Ok(()) => log::info!(
+                "Removed dependency on `{}` from `{}`",
+                required.name,
+                recipe.name
+            ),
+            Err(message) => log::error!("{}", message),
+        }
+    }
+
+    let mut failures = 0;
+    for recipe in matches.iter() {
+        match ladle::recipe_delete(origin, &recipe.id).await {
+            Ok(()) => log::info!("Deleted recipe `{}` ({})", recipe.name, recipe.id),
+            Err(message) => {
+                failures += 1;
+                log::error!("Failed to delete recipe `{}`: {}", recipe.name, message)
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "Failed to delete {} recipes",
+            failures
+        ))));
+    }
+
+    Ok(())
 }
 
 async fn requirement_add(
@@ -502,11 +2542,19 @@ async fn requirement_add(
     ingredient_clue: &str,
     quantity: &str,
     optional: bool,
+    create_ingredient: Option<Classifications>,
 ) -> Result<(), Box<dyn error::Error>> {
     let recipe = recipe_identify(origin, recipe_clue).await?;
-    let ingredient = ingredient_identify(origin, ingredient_clue, false).await?;
+    let ingredient =
+        ingredient_identify(origin, ingredient_clue, create_ingredient.as_ref()).await?;
 
-    ladle::requirement_create(origin, &recipe.id, &ingredient.id, quantity, optional).await
+    let requirement = ladle::NewRequirement {
+        recipe_id: &recipe.id,
+        ingredient_id: &ingredient.id,
+        quantity,
+        optional,
+    };
+    ladle::requirement_add(origin, requirement).await
 }
 
 async fn requirement_update(
@@ -517,18 +2565,203 @@ async fn requirement_update(
     optional: Option<bool>,
 ) -> Result<(), Box<dyn error::Error>> {
     let recipe = recipe_identify(origin, recipe_clue).await?;
-    let ingredient = ingredient_identify(origin, ingredient_clue, false).await?;
+    let ingredient = ingredient_identify(origin, ingredient_clue, None).await?;
 
     ladle::requirement_update(origin, &recipe.id, &ingredient.id, quantity, optional).await
 }
 
+/// Parse a requirement line of the form `ingredient: quantity [optional]`
+fn parse_requirement_line(line: &str) -> Option<(&str, &str, bool)> {
+    let (ingredient, rest) = line.split_once(':')?;
+    let rest = rest.trim();
+
+    let (quantity, optional) = match rest.strip_suffix("optional") {
+        Some(quantity) if quantity.is_empty() || quantity.ends_with(char::is_whitespace) => {
+            (quantity.trim(), true)
+        }
+        _ => (rest, false),
+    };
+
+    Some((ingredient.trim(), quantity, optional))
+}
+
+async fn requirement_sync(
+    origin: &str,
+    recipe_clue: &str,
+    path: &Path,
+) -> Result<(), Box<dyn error::Error>> {
+    let recipe = recipe_identify(origin, recipe_clue).await?;
+    let contents = fs::read_to_string(path)?;
+
+    let mut desired = HashMap::new();
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (clue, quantity, optional) = parse_requirement_line(line).ok_or_else(|| {
+            ChopstickError(format!(
+                "{}:{}: expected `ingredient: quantity [optional]`",
+                path.display(),
+                number + 1
+            ))
+        })?;
+
+        let ingredient = ingredient_identify(origin, clue, None).await?;
+        if desired
+            .insert(ingredient.id.clone(), (ingredient, quantity, optional))
+            .is_some()
+        {
+            return Err(Box::new(ChopstickError(format!(
+                "{}:{}: ingredient `{}` is listed more than once",
+                path.display(),
+                number + 1,
+                clue
+            ))));
+        }
+    }
+
+    let current = ladle::recipe_get_requirements(origin, &recipe.id)
+        .await?
+        .into_iter()
+        .map(|r| (r.ingredient.id.clone(), r))
+        .collect::<HashMap<_, _>>();
+
+    let mut failures = 0;
+    let mut report =
+        |action: &str, name: &str, result: Result<(), Box<dyn error::Error>>| match result {
+            Ok(()) => println!("{} {}", action, name),
+            Err(message) => {
+                failures += 1;
+                log::error!("Failed to {} requirement `{}`: {}", action, name, message)
+            }
+        };
+
+    for (id, requirement) in current.iter() {
+        if !desired.contains_key(id) {
+            let result = ladle::requirement_delete(origin, &recipe.id, id).await;
+            report("delete", &requirement.ingredient.name, result);
+        }
+    }
+
+    for (id, (ingredient, quantity, optional)) in desired.iter() {
+        match current.get(id) {
+            None => {
+                let requirement = ladle::NewRequirement {
+                    recipe_id: &recipe.id,
+                    ingredient_id: id,
+                    quantity,
+                    optional: *optional,
+                };
+                let result = ladle::requirement_add(origin, requirement).await;
+                report("create", &ingredient.name, result);
+            }
+            Some(requirement)
+                if requirement.quantity != *quantity || requirement.optional != *optional =>
+            {
+                let result = ladle::requirement_update(
+                    origin,
+                    &recipe.id,
+                    id,
+                    Some(quantity),
+                    Some(*optional),
+                )
+                .await;
+                report("update", &ingredient.name, result);
+            }
+            Some(_) => (),
+        }
+    }
+
+    if failures > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "Failed to apply {} requirement changes",
+            failures
+        ))));
+    }
+
+    Ok(())
+}
+
+async fn requirement_set_optional(
+    origin: &str,
+    recipe_clue: &str,
+    optional: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let recipe = recipe_identify(origin, recipe_clue).await?;
+    let requirements = ladle::recipe_get_requirements(origin, &recipe.id).await?;
+
+    let changing: Vec<_> = requirements
+        .iter()
+        .filter(|r| r.optional != optional)
+        .collect();
+
+    if changing.is_empty() {
+        log::info!(
+            "Every requirement of `{}` already has optional status {}",
+            recipe.name,
+            optional
+        );
+        return Ok(());
+    }
+
+    if dry_run {
+        for requirement in changing.iter() {
+            println!(
+                "{}\t{}",
+                requirement.ingredient.id, requirement.ingredient.name
+            );
+        }
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    for requirement in changing.iter() {
+        match ladle::requirement_update(
+            origin,
+            &recipe.id,
+            &requirement.ingredient.id,
+            None,
+            Some(optional),
+        )
+        .await
+        {
+            Ok(()) => log::debug!(
+                "Updated requirement `{}` of `{}`",
+                requirement.ingredient.name,
+                recipe.name
+            ),
+            Err(message) => {
+                failures += 1;
+                log::error!(
+                    "Failed to update requirement `{}`: {}",
+                    requirement.ingredient.name,
+                    message
+                )
+            }
+        }
+    }
+
+    log::info!("Updated {} requirements", changing.len() - failures);
+
+    if failures > 0 {
+        return Err(Box::new(ChopstickError(format!(
+            "Failed to update {} requirements",
+            failures
+        ))));
+    }
+
+    Ok(())
+}
+
 async fn requirement_delete(
     origin: &str,
     recipe_clue: &str,
     ingredient_clue: &str,
 ) -> Result<(), Box<dyn error::Error>> {
     let recipe = recipe_identify(origin, recipe_clue).await?;
-    let ingredient = ingredient_identify(origin, ingredient_clue, false).await?;
+    let ingredient = ingredient_identify(origin, ingredient_clue, None).await?;
 
     ladle::requirement_delete(origin, &recipe.id, &ingredient.id).await
 }
@@ -543,14 +2776,15 @@ async fn dependency_create(
     let recipe = recipe_identify(origin, recipe_clue).await?;
     let required = recipe_identify(origin, required_clue).await?;
 
-    ladle::dependency_create(
-        origin,
-        &recipe.id,
-        &required.id,
-        &quantity.unwrap_or(""),
+    let dependency = ladle::NewDependency {
+        recipe_id: &recipe.id,
+        required_id: &required.id,
+        quantity: quantity
+            .or(DEFAULT_DEPENDENCY_QUANTITY.get().map(String::as_str))
+            .unwrap_or(DEPENDENCY_QUANTITY),
         optional,
-    )
-    .await
+    };
+    ladle::dependency_add(origin, dependency).await
 }
 
 async fn dependency_edit(
@@ -584,7 +2818,7 @@ async fn recipe_tag(
 ) -> Result<(), Box<dyn error::Error>> {
     let recipe = recipe_identify(origin, recipe_clue).await?;
 
-    ladle::recipe_tag(origin, &recipe.id, &label).await
+    ladle::recipe_tag(origin, &recipe.id, label).await
 }
 
 async fn recipe_untag(
@@ -598,7 +2832,39 @@ async fn recipe_untag(
     ladle::recipe_untag(origin, &recipe.id, &label.id).await
 }
 
-async fn recipe_identify(url: &str, clue: &str) -> Result<RecipeIndex, Box<dyn error::Error>> {
+async fn recipe_retag(
+    origin: &str,
+    recipe_clue: &str,
+    old_label_clue: &str,
+    new_label: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    let recipe = recipe_identify(origin, recipe_clue).await?;
+    let old_label = label_identify(origin, old_label_clue, false).await?;
+
+    if !ladle::recipe_get(origin, &recipe.id)
+        .await?
+        .tags
+        .contains(&old_label)
+    {
+        return Err(Box::new(ChopstickError(format!(
+            "Recipe `{}` is not tagged with `{}`",
+            recipe.name, old_label.name
+        ))));
+    }
+
+    ladle::recipe_untag(origin, &recipe.id, &old_label.id).await?;
+    ladle::recipe_tag(origin, &recipe.id, new_label).await?;
+
+    log::info!(
+        "Replaced tag `{}` with `{}` on `{}`",
+        old_label.name,
+        new_label,
+        recipe.name
+    );
+    Ok(())
+}
+
+pub async fn recipe_identify(url: &str, clue: &str) -> Result<RecipeIndex, Box<dyn error::Error>> {
     if let Ok(recipe) = ladle::recipe_get(url, clue).await {
         return Ok(RecipeIndex {
             id: recipe.id,
@@ -616,10 +2882,18 @@ async fn recipe_identify(url: &str, clue: &str) -> Result<RecipeIndex, Box<dyn e
         return Ok(recipe.to_owned());
     }
 
-    for indice in matches.iter() {
-        if indice.name == clue {
-            return Ok(indice.to_owned());
+    if let Some(indice) = find_named(&matches, |i| &i.name, clue) {
+        if indice.name != clue {
+            log::info!("Identified recipe `{}` from `{}`", indice.name, clue);
         }
+        return Ok(indice.to_owned());
+    }
+
+    if matches.is_empty() {
+        return Err(Box::new(NotFoundError(format!(
+            "No recipe with id/name `{}`",
+            clue
+        ))));
     }
 
     Err(Box::new(MatchingError(
@@ -627,3 +2901,57 @@ async fn recipe_identify(url: &str, clue: &str) -> Result<RecipeIndex, Box<dyn e
         matches.iter().map(|r| r.name.clone()).collect(),
     )))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_edited_trailing_newline() {
+        assert_eq!(
+            normalize_edited("Serve hot", String::from("Serve hot\n")),
+            "Serve hot"
+        );
+        assert_eq!(
+            normalize_edited("Serve hot", String::from("Serve cold\n")),
+            "Serve cold"
+        );
+    }
+
+    #[test]
+    fn normalize_edited_multiple_trailing_newlines() {
+        assert_eq!(
+            normalize_edited("Serve hot", String::from("Serve hot\n\n")),
+            "Serve hot\n"
+        );
+        assert_eq!(
+            normalize_edited("Serve hot\n", String::from("Serve hot\n\n")),
+            "Serve hot\n\n"
+        );
+    }
+
+    #[test]
+    fn normalize_edited_crlf() {
+        assert_eq!(
+            normalize_edited("Serve hot", String::from("Serve hot\r\n")),
+            "Serve hot"
+        );
+        assert_eq!(
+            normalize_edited("Serve\r\nhot", String::from("Serve\r\nhot\r\n")),
+            "Serve\r\nhot"
+        );
+    }
+
+    #[test]
+    fn normalize_edited_unchanged() {
+        assert_eq!(
+            normalize_edited("Serve hot", String::from("Serve hot")),
+            "Serve hot"
+        );
+        assert_eq!(
+            normalize_edited("Serve hot\n", String::from("Serve hot\n")),
+            "Serve hot\n"
+        );
+        assert_eq!(normalize_edited("", String::from("\n")), "");
+    }
+}
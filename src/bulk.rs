@@ -0,0 +1,83 @@
+use crate::models::{Ingredient, Label, Recipe};
+use futures::stream::{self, StreamExt};
+use std::collections::HashSet;
+use std::error::Error;
+use std::future::Future;
+use std::hash::Hash;
+
+/// Number of requests in flight at once when fetching entities in bulk
+pub const CONCURRENCY: usize = 16;
+
+/// Every recipe, ingredient and label of a server
+#[derive(Debug, Default)]
+pub struct Everything {
+    pub recipes: HashSet<Recipe>,
+    pub ingredients: HashSet<Ingredient>,
+    pub labels: HashSet<Label>,
+
+    /// Messages of the entity fetches that failed. The matching entities are missing from the
+    /// sets above
+    pub failures: Vec<String>,
+}
+
+/// Run `fetches` with bounded concurrency, collecting the fetched entities and the error messages
+/// of the failed fetches
+async fn gather<T, F>(fetches: impl Iterator<Item = F>) -> (HashSet<T>, Vec<String>)
+where
+    T: Hash + Eq,
+    F: Future<Output = Result<T, Box<dyn Error>>>,
+{
+    let mut fetched = HashSet::new();
+    let mut failures = vec![];
+
+    let mut responses = stream::iter(fetches).buffer_unordered(CONCURRENCY);
+    while let Some(response) = responses.next().await {
+        match response {
+            Ok(entity) => {
+                fetched.insert(entity);
+            }
+            Err(message) => failures.push(message.to_string()),
+        }
+    }
+
+    (fetched, failures)
+}
+
+/// Fetch every recipe of the server. Failed fetches are returned as error messages
+pub async fn fetch_recipes(url: &str) -> Result<(HashSet<Recipe>, Vec<String>), Box<dyn Error>> {
+    let index = crate::recipe_index(url, "").await?;
+    Ok(gather(index.iter().map(|r| crate::recipe_get(url, &r.id))).await)
+}
+
+/// Fetch every ingredient of the server. Failed fetches are returned as error messages
+pub async fn fetch_ingredients(
+    url: &str,
+) -> Result<(HashSet<Ingredient>, Vec<String>), Box<dyn Error>> {
+    let index = crate::ingredient_index(url, "").await?;
+    Ok(gather(index.iter().map(|i| crate::ingredient_get(url, &i.id))).await)
+}
+
+/// Fetch every label of the server. Failed fetches are returned as error messages
+pub async fn fetch_labels(url: &str) -> Result<(HashSet<Label>, Vec<String>), Box<dyn Error>> {
+    let index = crate::label_index(url, "").await?;
+    Ok(gather(index.iter().map(|l| crate::label_get(url, &l.id))).await)
+}
+
+/// Fetch every recipe, ingredient and label of the server. Failing to list an entity type is an
+/// error, while failing to fetch a single entity is recorded in [`Everything::failures`]
+pub async fn fetch_all(url: &str) -> Result<Everything, Box<dyn Error>> {
+    let (recipes, mut failures) = fetch_recipes(url).await?;
+
+    let (ingredients, ingredient_failures) = fetch_ingredients(url).await?;
+    failures.extend(ingredient_failures);
+
+    let (labels, label_failures) = fetch_labels(url).await?;
+    failures.extend(label_failures);
+
+    Ok(Everything {
+        recipes,
+        ingredients,
+        labels,
+        failures,
+    })
+}
@@ -1,11 +1,19 @@
-use reqwest::{Client, StatusCode};
-use serde::Serialize;
+use futures::stream::{self, StreamExt};
+use reqwest::{header, redirect, Client, Response, StatusCode};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
 use std::any::Any;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
+pub mod bulk;
 pub mod models;
+mod offline;
+pub mod quantity;
 
 #[derive(Debug)]
 struct KnifeError(StatusCode, String);
@@ -29,34 +37,335 @@ impl fmt::Display for LadleError {
 
 impl Error for LadleError {}
 
-async fn get<'a, T: serde::de::DeserializeOwned>(url: &str) -> Result<T, Box<dyn Error>> {
-    let client = Client::new();
+#[derive(Debug)]
+struct OfflineError(String);
 
-    let response = client.get(url).send().await?;
-    let status_code = response.status();
+impl fmt::Display for OfflineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cannot modify data when working from a dump: {}", self.0)
+    }
+}
 
-    log::debug!("GET {} -> {}", url, status_code);
+impl Error for OfflineError {}
 
-    let answer = response.json::<models::Answer<T>>().await?;
+/// Error returned when the server answers a request with 404 NOT FOUND. Holds the requested URL.
+#[derive(Debug)]
+pub struct NotFoundError(pub String);
+
+impl fmt::Display for NotFoundError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Not found: {}", self.0)
+    }
+}
+
+impl Error for NotFoundError {}
+
+static REQUEST_COUNT: AtomicUsize = AtomicUsize::new(0);
+static REQUEST_MICROS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of requests sent to knife servers since the program started
+pub fn request_count() -> usize {
+    REQUEST_COUNT.load(Ordering::Relaxed)
+}
+
+/// Cumulated time spent waiting on knife servers since the program started. Concurrent requests
+/// are all accounted for, so this can exceed the wall time.
+pub fn request_time() -> Duration {
+    Duration::from_micros(REQUEST_MICROS.load(Ordering::Relaxed))
+}
+
+fn record_request(start: Instant) {
+    REQUEST_COUNT.fetch_add(1, Ordering::Relaxed);
+    REQUEST_MICROS.fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+}
+
+static REDIRECT_LIMIT: AtomicUsize = AtomicUsize::new(10);
+static WRITE_REDIRECTS: AtomicBool = AtomicBool::new(false);
+
+/// Set the maximum number of redirects followed by a request
+pub fn set_redirect_limit(limit: usize) {
+    REDIRECT_LIMIT.store(limit, Ordering::Relaxed);
+}
+
+/// Enable or disable following redirects on write requests. Disabled by default, as clients may
+/// change the method or drop the body of a redirected request. A redirected write request then
+/// fails instead.
+pub fn set_write_redirects(enabled: bool) {
+    WRITE_REDIRECTS.store(enabled, Ordering::Relaxed);
+}
+
+static USER_AGENT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Set the `User-Agent` header sent with requests. Defaults to `ladle/<version>`
+pub fn set_user_agent(agent: &str) {
+    *USER_AGENT.lock().unwrap() = Some(agent.to_string());
+}
+
+/// Build a client with the configured redirect policy and user agent. Redirects are logged as
+/// they happen.
+fn client(write: bool) -> Result<Client, Box<dyn Error>> {
+    let limit = if write && !WRITE_REDIRECTS.load(Ordering::Relaxed) {
+        0
+    } else {
+        REDIRECT_LIMIT.load(Ordering::Relaxed)
+    };
+
+    let policy = redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() > limit {
+            attempt.stop()
+        } else {
+            log::debug!(
+                "Redirected from {} to {}",
+                attempt.previous().last().map_or("", |u| u.as_str()),
+                attempt.url()
+            );
+            attempt.follow()
+        }
+    });
+
+    let agent = USER_AGENT
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| format!("ladle/{}", env!("CARGO_PKG_VERSION")));
+
+    Ok(Client::builder()
+        .redirect(policy)
+        .user_agent(agent)
+        .build()?)
+}
+
+/// Collapse repeated slashes in the path of `url`, left when joining a base URL ending with a slash
+/// to an endpoint path. Base URLs may carry a path prefix, as in `https://host/api/v1`
+fn normalize_url(url: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let (path, query) = match rest.find('?') {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+
+    let mut normalized = String::with_capacity(url.len());
+    if !scheme.is_empty() {
+        normalized.push_str(scheme);
+        normalized.push_str("://");
+    }
+    for c in path.chars() {
+        if !(c == '/' && normalized.ends_with('/') && !normalized.ends_with("://")) {
+            normalized.push(c);
+        }
+    }
+    normalized.push_str(query);
+    normalized
+}
+
+/// Error out on a redirect that was not followed, before attempting to interpret the body
+fn check_redirect(url: &str, response: &Response) -> Result<(), Box<dyn Error>> {
+    if response.status().is_redirection() {
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|l| l.to_str().ok())
+            .unwrap_or("an unknown location");
+        return Err(Box::new(LadleError(format!(
+            "Server redirected {} to {}, refusing to follow. Use the final URL instead",
+            url, location
+        ))));
+    }
+
+    Ok(())
+}
+
+static RETRY_EMPTY: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable retrying once a read the server answered successfully but without data, as
+/// some servers momentarily do. Writes are never retried
+pub fn set_retry_empty(enabled: bool) {
+    RETRY_EMPTY.store(enabled, Ordering::Relaxed);
+}
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable dry runs. During a dry run, write requests are logged instead of being sent
+/// and succeed with default data. Read requests are sent as usual.
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+static OFFLINE: Mutex<Option<offline::Store>> = Mutex::new(None);
+
+/// Answer requests addressed to `base` from the given data instead of a knife server. Write
+/// requests fail in this mode.
+pub fn set_offline(
+    base: &str,
+    recipes: Vec<models::Recipe>,
+    ingredients: Vec<models::Ingredient>,
+    labels: Vec<models::Label>,
+) {
+    *OFFLINE.lock().unwrap() = Some(offline::Store {
+        base: base.to_string(),
+        recipes,
+        ingredients,
+        labels,
+    });
+}
+
+fn offline_get(url: &str) -> Option<Result<Value, Box<dyn Error>>> {
+    let offline = OFFLINE.lock().unwrap();
+    let store = offline.as_ref()?;
+
+    log::debug!("GET {} -> offline", url);
+    Some(
+        store
+            .get(url)
+            .ok_or_else(|| Box::new(NotFoundError(url.to_string())) as Box<dyn Error>),
+    )
+}
+
+fn offline_check(url: &str) -> Result<(), Box<dyn Error>> {
+    match OFFLINE.lock().unwrap().as_ref() {
+        Some(_) => Err(Box::new(OfflineError(url.to_string()))),
+        None => Ok(()),
+    }
+}
+
+static CACHE: Mutex<Option<HashMap<String, Value>>> = Mutex::new(None);
+
+/// Enable or disable caching of GET requests. When enabled, the data of successful GET requests is
+/// kept in memory and returned for subsequent requests of the same URL. Any write request clears
+/// the cache.
+pub fn set_caching(enabled: bool) {
+    *CACHE.lock().unwrap() = enabled.then(HashMap::new);
+}
+
+/// Drop all cached data, forcing the next requests to hit the server
+pub fn cache_clear() {
+    if let Some(cache) = CACHE.lock().unwrap().as_mut() {
+        cache.clear();
+    }
+}
+
+fn cache_lookup(url: &str) -> Option<Value> {
+    CACHE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|cache| cache.get(url).cloned())
+}
+
+fn cache_insert(url: &str, object: &Value) {
+    if let Some(cache) = CACHE.lock().unwrap().as_mut() {
+        cache.insert(url.to_string(), object.clone());
+    }
+}
+
+async fn get<T: serde::de::DeserializeOwned>(url: &str) -> Result<T, Box<dyn Error>> {
+    if let Some(object) = offline_get(url) {
+        return Ok(serde_json::from_value(object?)?);
+    }
+
+    if let Some(object) = cache_lookup(url) {
+        log::debug!("GET {} -> cached", url);
+        return Ok(serde_json::from_value(object)?);
+    }
+
+    let client = client(false)?;
+
+    let mut attempts = 0;
+    let (status_code, answer) = loop {
+        attempts += 1;
+
+        let start = Instant::now();
+        let response = client.get(normalize_url(url)).send().await;
+        record_request(start);
+        let response = response?;
+        check_redirect(url, &response)?;
+        let status_code = response.status();
+
+        log::debug!("GET {} -> {}", url, status_code);
+
+        let answer = response.json::<models::Answer<Value>>().await?;
+
+        let empty = status_code == StatusCode::OK && answer.data.is_none();
+        if empty && attempts == 1 && RETRY_EMPTY.load(Ordering::Relaxed) {
+            log::debug!("GET {} answered without data, retrying", url);
+            continue;
+        }
+
+        break (status_code, answer);
+    };
 
     match (status_code, answer.data) {
-        (StatusCode::OK, Some(object)) => Ok(object),
+        (StatusCode::OK, Some(object)) => {
+            cache_insert(url, &object);
+            Ok(serde_json::from_value(object)?)
+        }
         (StatusCode::OK, None) => Err(Box::new(LadleError(String::from(
             "Failed to interpret the server's response",
         )))),
+        (StatusCode::NOT_FOUND, _) => Err(Box::new(NotFoundError(url.to_string()))),
         (status, _) => Err(Box::new(KnifeError(status, answer.error))),
     }
 }
 
+/// Send a GET request and return the body of the response as is, whatever its status. Bypasses
+/// the cache
+async fn get_raw(url: &str) -> Result<String, Box<dyn Error>> {
+    if OFFLINE.lock().unwrap().is_some() {
+        return Err(Box::new(LadleError(format!(
+            "No server response to show when working from a dump: {}",
+            url
+        ))));
+    }
+
+    let client = client(false)?;
+
+    let start = Instant::now();
+    let response = client.get(normalize_url(url)).send().await;
+    record_request(start);
+    let response = response?;
+    check_redirect(url, &response)?;
+
+    log::debug!("GET {} -> {} (raw)", url, response.status());
+
+    Ok(response.text().await?)
+}
+
 /// Send a POST request to a knife server. Hijack the 409 CONFLICT status to get info on existing
 /// data
-async fn post<'a, P: Serialize + fmt::Debug, T: serde::de::DeserializeOwned + Any + Default>(
+async fn post<P: Serialize + fmt::Debug, T: serde::de::DeserializeOwned + Any + Default>(
     url: &str,
     params: P,
 ) -> Result<T, Box<dyn Error>> {
-    let client = Client::new();
+    Ok(post_creation(url, params).await?.object)
+}
+
+/// Send a POST request to a knife server, recording whether the object was created or already
+/// existed
+async fn post_creation<
+    P: Serialize + fmt::Debug,
+    T: serde::de::DeserializeOwned + Any + Default,
+>(
+    url: &str,
+    params: P,
+) -> Result<models::Creation<T>, Box<dyn Error>> {
+    offline_check(url)?;
+
+    if DRY_RUN.load(Ordering::Relaxed) {
+        log::info!("Dry run: POST {} {:?}", url, params);
+        return Ok(models::Creation {
+            object: T::default(),
+            created: true,
+        });
+    }
+
+    let client = client(true)?;
+    cache_clear();
 
-    let response = client.post(url).json(&params).send().await?;
+    let start = Instant::now();
+    let response = client.post(normalize_url(url)).json(&params).send().await;
+    record_request(start);
+    let response = response?;
+    check_redirect(url, &response)?;
     let status_code = response.status();
 
     log::debug!("POST {} {:?} -> {}", url, params, status_code);
@@ -64,21 +373,44 @@ async fn post<'a, P: Serialize + fmt::Debug, T: serde::de::DeserializeOwned + An
     let answer = response.json::<models::Answer<T>>().await?;
 
     match (status_code, answer.data) {
-        (StatusCode::OK, Some(object))
-        | (StatusCode::CREATED, Some(object))
-        | (StatusCode::CONFLICT, Some(object)) => Ok(object),
-        (StatusCode::OK, None) | (StatusCode::CREATED, None) => Ok(T::default()),
+        (StatusCode::OK, Some(object)) | (StatusCode::CREATED, Some(object)) => {
+            Ok(models::Creation {
+                object,
+                created: true,
+            })
+        }
+        (StatusCode::CONFLICT, Some(object)) => Ok(models::Creation {
+            object,
+            created: false,
+        }),
+        (StatusCode::OK, None) | (StatusCode::CREATED, None) => Ok(models::Creation {
+            object: T::default(),
+            created: true,
+        }),
+        (StatusCode::NOT_FOUND, _) => Err(Box::new(NotFoundError(url.to_string()))),
         (status, _) => Err(Box::new(KnifeError(status, answer.error))),
     }
 }
 
-async fn put<'a, P: Serialize + fmt::Debug, T: serde::de::DeserializeOwned + Any + Default>(
+async fn put<P: Serialize + fmt::Debug, T: serde::de::DeserializeOwned + Any + Default>(
     url: &str,
     params: P,
 ) -> Result<T, Box<dyn Error>> {
-    let client = Client::new();
+    offline_check(url)?;
+
+    if DRY_RUN.load(Ordering::Relaxed) {
+        log::info!("Dry run: PUT {} {:?}", url, params);
+        return Ok(T::default());
+    }
 
-    let response = client.put(url).json(&params).send().await?;
+    let client = client(true)?;
+    cache_clear();
+
+    let start = Instant::now();
+    let response = client.put(normalize_url(url)).json(&params).send().await;
+    record_request(start);
+    let response = response?;
+    check_redirect(url, &response)?;
     let status_code = response.status();
 
     log::debug!("PUT {} {:?} -> {}", url, params, status_code);
@@ -93,14 +425,27 @@ async fn put<'a, P: Serialize + fmt::Debug, T: serde::de::DeserializeOwned + Any
         (StatusCode::OK, None) | (StatusCode::CREATED, None) | (StatusCode::ACCEPTED, None) => {
             Ok(T::default())
         }
+        (StatusCode::NOT_FOUND, _) => Err(Box::new(NotFoundError(url.to_string()))),
         (status, _) => Err(Box::new(KnifeError(status, answer.error))),
     }
 }
 
 async fn delete(url: &str) -> Result<(), Box<dyn Error>> {
-    let client = Client::new();
+    offline_check(url)?;
+
+    if DRY_RUN.load(Ordering::Relaxed) {
+        log::info!("Dry run: DELETE {}", url);
+        return Ok(());
+    }
 
-    let response = client.delete(url).send().await?;
+    let client = client(true)?;
+    cache_clear();
+
+    let start = Instant::now();
+    let response = client.delete(normalize_url(url)).send().await;
+    record_request(start);
+    let response = response?;
+    check_redirect(url, &response)?;
     let status_code = response.status();
 
     log::debug!("DELETE {} -> {}", url, status_code);
@@ -109,6 +454,7 @@ async fn delete(url: &str) -> Result<(), Box<dyn Error>> {
 
     match (status_code, answer.data) {
         (StatusCode::OK, _) => Ok(()),
+        (StatusCode::NOT_FOUND, _) => Err(Box::new(NotFoundError(url.to_string()))),
         (status, _) => Err(Box::new(KnifeError(status, answer.error))),
     }
 }
@@ -123,6 +469,12 @@ pub async fn recipe_index(
     answer.await
 }
 
+/// Body of the server's response to a recipe request, unparsed
+pub async fn recipe_get_raw(url: &str, id: &str) -> Result<String, Box<dyn Error>> {
+    let endpoint = format!("{}/recipes/{}", url, id);
+    get_raw(&endpoint).await
+}
+
 pub async fn recipe_get(url: &str, id: &str) -> Result<models::Recipe, Box<dyn Error>> {
     let endpoint = format!("{}/recipes/{}", url, id);
     let answer = get::<models::Recipe>(&endpoint);
@@ -137,16 +489,72 @@ pub async fn recipe_create(
     directions: &str,
     information: &str,
 ) -> Result<models::Recipe, Box<dyn Error>> {
-    let params = json!({
+    recipe_create_extra(
+        url,
+        name,
+        author,
+        directions,
+        information,
+        None,
+        &Map::default(),
+    )
+    .await
+}
+
+/// Fields of the recipe requests with a dedicated parameter
+const RECIPE_FIELDS: [&str; 6] = ["id", "name", "author", "directions", "information", "image"];
+
+/// Add fields ladle does not model to the body of a recipe request. Fields with a dedicated
+/// parameter are refused, to avoid setting them twice
+fn merge_extra(params: &mut Value, extra: &Map<String, Value>) -> Result<(), Box<dyn Error>> {
+    if let Some(key) = extra
+        .keys()
+        .find(|key| RECIPE_FIELDS.contains(&key.as_str()))
+    {
+        return Err(Box::new(LadleError(format!(
+            "Field `{}` has a dedicated parameter and cannot be set as an extra field",
+            key
+        ))));
+    }
+
+    params.as_object_mut().unwrap().extend(
+        extra
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone())),
+    );
+    Ok(())
+}
+
+/// Create a recipe with an optional image URL, sending the fields of `extra` along with the
+/// modelled ones
+pub async fn recipe_create_extra(
+    url: &str,
+    name: &str,
+    author: &str,
+    directions: &str,
+    information: &str,
+    image: Option<&str>,
+    extra: &Map<String, Value>,
+) -> Result<models::Recipe, Box<dyn Error>> {
+    let mut params = json!({
         "name": name,
         "author": author,
         "directions": directions,
         "information": information
     });
+    if let Some(value) = image {
+        params
+            .as_object_mut()
+            .unwrap()
+            .insert(String::from("image"), Value::String(String::from(value)));
+    }
+    merge_extra(&mut params, extra)?;
     let endpoint = format!("{}/recipes/new", url);
     post(&endpoint, params).await
 }
 
+/// Update a recipe's fields. Fields passed as `None` are left unchanged, while `Some("")` sets them
+/// to an empty value.
 pub async fn recipe_update(
     url: &str,
     id: &str,
@@ -154,32 +562,51 @@ pub async fn recipe_update(
     author: Option<&str>,
     directions: Option<&str>,
     information: Option<&str>,
+) -> Result<models::Recipe, Box<dyn Error>> {
+    let update = RecipeUpdate {
+        name,
+        author,
+        directions,
+        information,
+        image: None,
+    };
+    recipe_update_extra(url, id, update, &Map::default()).await
+}
+
+/// Modelled fields of a recipe update. Fields set to `None` are left unchanged
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RecipeUpdate<'a> {
+    pub name: Option<&'a str>,
+    pub author: Option<&'a str>,
+    pub directions: Option<&'a str>,
+    pub information: Option<&'a str>,
+    pub image: Option<&'a str>,
+}
+
+/// Update a recipe, sending the fields of `extra` along with the modelled ones
+pub async fn recipe_update_extra(
+    url: &str,
+    id: &str,
+    update: RecipeUpdate<'_>,
+    extra: &Map<String, Value>,
 ) -> Result<models::Recipe, Box<dyn Error>> {
     let mut params = Value::Object(Map::default());
-    if let Some(value) = name {
-        params
-            .as_object_mut()
-            .unwrap()
-            .insert(String::from("name"), Value::String(String::from(value)));
-    }
-    if let Some(value) = author {
-        params
-            .as_object_mut()
-            .unwrap()
-            .insert(String::from("author"), Value::String(String::from(value)));
-    }
-    if let Some(value) = directions {
-        params.as_object_mut().unwrap().insert(
-            String::from("directions"),
-            Value::String(String::from(value)),
-        );
-    }
-    if let Some(value) = information {
-        params.as_object_mut().unwrap().insert(
-            String::from("information"),
-            Value::String(String::from(value)),
-        );
+    let fields = [
+        ("name", update.name),
+        ("author", update.author),
+        ("directions", update.directions),
+        ("information", update.information),
+        ("image", update.image),
+    ];
+    for (field, value) in fields.into_iter() {
+        if let Some(value) = value {
+            params
+                .as_object_mut()
+                .unwrap()
+                .insert(String::from(field), Value::String(String::from(value)));
+        }
     }
+    merge_extra(&mut params, extra)?;
 
     let endpoint = format!("{}/recipes/{}", url, id);
     put(&endpoint, params).await
@@ -192,6 +619,29 @@ pub async fn recipe_delete(url: &str, id: &str) -> Result<(), Box<dyn Error>> {
     answer.await
 }
 
+/// Dependency to add to a recipe
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct NewDependency<'a> {
+    /// Recipe to add the dependency to
+    #[serde(skip)]
+    pub recipe_id: &'a str,
+
+    /// Recipe depended on
+    #[serde(rename = "requisite")]
+    pub required_id: &'a str,
+
+    pub quantity: &'a str,
+    pub optional: bool,
+}
+
+pub async fn dependency_add(
+    url: &str,
+    dependency: NewDependency<'_>,
+) -> Result<(), Box<dyn Error>> {
+    let endpoint = format!("{}/recipes/{}/dependencies/add", url, dependency.recipe_id);
+    post(&endpoint, dependency).await
+}
+
 pub async fn dependency_create(
     url: &str,
     id: &str,
@@ -199,15 +649,16 @@ pub async fn dependency_create(
     quantity: &str,
     optional: bool,
 ) -> Result<(), Box<dyn Error>> {
-    let params = json!({
-        "requisite": required_id,
-        "quantity": quantity,
-        "optional": optional,
-    });
-    let endpoint = format!("{}/recipes/{}/dependencies/add", url, id);
-    let answer = post(&endpoint, params);
-
-    answer.await
+    dependency_add(
+        url,
+        NewDependency {
+            recipe_id: id,
+            required_id,
+            quantity,
+            optional,
+        },
+    )
+    .await
 }
 
 pub async fn dependency_edit(
@@ -271,6 +722,62 @@ pub async fn ingredient_index(
     get(&endpoint).await
 }
 
+/// Index entry as returned by servers supporting the `fields` query parameter. Servers ignoring
+/// it answer without `used`.
+#[derive(Deserialize)]
+struct PartialUsage {
+    id: String,
+    name: String,
+    used: Option<usize>,
+}
+
+/// List ingredients matching `pattern` along with the number of recipes using them. The usage is
+/// requested from the server in the index; if the server does not provide it, each ingredient is
+/// fetched to count its uses.
+pub async fn ingredient_index_with_usage(
+    url: &str,
+    pattern: &str,
+) -> Result<Vec<models::IngredientUsage>, Box<dyn Error>> {
+    let endpoint = format!("{}/ingredients?name={}&fields=used", url, pattern);
+    let index = get::<Vec<PartialUsage>>(&endpoint).await?;
+
+    if let Some(usage) = index
+        .iter()
+        .map(|i| {
+            i.used.map(|used| models::IngredientUsage {
+                id: i.id.clone(),
+                name: i.name.clone(),
+                used,
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+    {
+        return Ok(usage);
+    }
+
+    log::debug!("Server did not provide ingredient usage, fetching each ingredient");
+    let fetches = index.iter().map(|i| ingredient_get(url, &i.id));
+    stream::iter(fetches)
+        .buffered(bulk::CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .map(|ingredient| {
+            ingredient.map(|i| models::IngredientUsage {
+                used: i.used_in.len(),
+                id: i.id,
+                name: i.name,
+            })
+        })
+        .collect()
+}
+
+/// Body of the server's response to an ingredient request, unparsed
+pub async fn ingredient_get_raw(url: &str, id: &str) -> Result<String, Box<dyn Error>> {
+    let endpoint = format!("{}/ingredients/{}", url, id);
+    get_raw(&endpoint).await
+}
+
 pub async fn ingredient_get(url: &str, id: &str) -> Result<models::Ingredient, Box<dyn Error>> {
     let endpoint = format!("{}/ingredients/{}", url, id);
     get(&endpoint).await
@@ -284,6 +791,22 @@ pub async fn ingredient_create(
     gluten: bool,
     animal_product: bool,
 ) -> Result<models::IngredientIndex, Box<dyn Error>> {
+    Ok(
+        ingredient_create_checked(url, name, dairy, meat, gluten, animal_product)
+            .await?
+            .object,
+    )
+}
+
+/// Create an ingredient, telling apart a new ingredient from an existing one of the same name
+pub async fn ingredient_create_checked(
+    url: &str,
+    name: &str,
+    dairy: bool,
+    meat: bool,
+    gluten: bool,
+    animal_product: bool,
+) -> Result<models::Creation<models::IngredientIndex>, Box<dyn Error>> {
     let params = json!({
         "name": name,
         "dairy": dairy,
@@ -293,7 +816,7 @@ pub async fn ingredient_create(
     });
     let endpoint = format!("{}/ingredients/new", url);
 
-    post(&endpoint, params).await
+    post_creation(&endpoint, params).await
 }
 
 pub async fn ingredient_update(
@@ -359,16 +882,69 @@ pub async fn label_index(
     get(&endpoint).await
 }
 
+/// Body of the server's response to a label request, unparsed
+pub async fn label_get_raw(url: &str, id: &str) -> Result<String, Box<dyn Error>> {
+    let endpoint = format!("{}/labels/{}", url, id);
+    get_raw(&endpoint).await
+}
+
+/// Version of the server, as reported by its `/version` endpoint. Servers without the endpoint,
+/// and dumps, have no known version
+pub async fn server_version(url: &str) -> Result<Option<String>, Box<dyn Error>> {
+    if OFFLINE.lock().unwrap().is_some() {
+        return Ok(None);
+    }
+
+    let endpoint = format!("{}/version", url);
+    let client = client(false)?;
+
+    let start = Instant::now();
+    let response = client.get(normalize_url(&endpoint)).send().await;
+    record_request(start);
+    let response = response?;
+    check_redirect(&endpoint, &response)?;
+    let status_code = response.status();
+
+    log::debug!("GET {} -> {}", endpoint, status_code);
+
+    if status_code == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let answer = response.json::<models::Answer<Value>>().await?;
+    match (status_code, answer.data) {
+        (StatusCode::OK, Some(Value::String(version))) => Ok(Some(version)),
+        (StatusCode::OK, Some(Value::Object(info))) => {
+            let field = |key: &str| info.get(key).and_then(Value::as_str);
+            Ok(match (field("version"), field("build")) {
+                (Some(version), Some(build)) => Some(format!("{} ({})", version, build)),
+                (Some(version), None) => Some(version.to_string()),
+                _ => Some(Value::Object(info).to_string()),
+            })
+        }
+        (StatusCode::OK, _) => Ok(None),
+        (status, _) => Err(Box::new(KnifeError(status, answer.error))),
+    }
+}
+
 pub async fn label_get(url: &str, id: &str) -> Result<models::Label, Box<dyn Error>> {
     let endpoint = format!("{}/labels/{}", url, id);
     get(&endpoint).await
 }
 
 pub async fn label_create(url: &str, name: &str) -> Result<models::LabelIndex, Box<dyn Error>> {
+    Ok(label_create_checked(url, name).await?.object)
+}
+
+/// Create a label, telling apart a new label from an existing one of the same name
+pub async fn label_create_checked(
+    url: &str,
+    name: &str,
+) -> Result<models::Creation<models::LabelIndex>, Box<dyn Error>> {
     let params = json!({ "name": name });
 
     let endpoint = format!("{}/labels/new", url);
-    post(&endpoint, params).await
+    post_creation(&endpoint, params).await
 }
 
 pub async fn label_update(
@@ -386,6 +962,28 @@ pub async fn label_delete(url: &str, id: &str) -> Result<(), Box<dyn Error>> {
     delete(&endpoint).await
 }
 
+/// Requirement to add to a recipe
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct NewRequirement<'a> {
+    /// Recipe to add the requirement to
+    #[serde(skip)]
+    pub recipe_id: &'a str,
+
+    /// Ingredient required
+    pub ingredient_id: &'a str,
+
+    pub quantity: &'a str,
+    pub optional: bool,
+}
+
+pub async fn requirement_add(
+    url: &str,
+    requirement: NewRequirement<'_>,
+) -> Result<(), Box<dyn Error>> {
+    let endpoint = format!("{}/recipes/{}/requirements/add", url, requirement.recipe_id);
+    post(&endpoint, requirement).await
+}
+
 pub async fn requirement_create(
     url: &str,
     recipe_id: &str,
@@ -393,13 +991,16 @@ pub async fn requirement_create(
     quantity: &str,
     optional: bool,
 ) -> Result<(), Box<dyn Error>> {
-    let params = json!({
-        "quantity": quantity,
-        "optional": optional,
-        "ingredient_id": ingredient_id,
-    });
-    let endpoint = format!("{}/recipes/{}/requirements/add", url, recipe_id);
-    post(&endpoint, params).await
+    requirement_add(
+        url,
+        NewRequirement {
+            recipe_id,
+            ingredient_id,
+            quantity,
+            optional,
+        },
+    )
+    .await
 }
 
 pub async fn requirement_update(
@@ -443,34 +1044,101 @@ pub async fn requirement_delete(
     delete(&endpoint).await
 }
 
-fn calc_missing(list: &Vec<models::Recipe>) -> Vec<String> {
+/// Ids of the recipes `list` depends on that are not part of it, in order of first appearance
+fn calc_missing(list: &[models::Recipe]) -> Vec<String> {
+    let fetched = list.iter().map(|r| r.id.as_str()).collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+
     list.iter()
-        .flat_map(|r| r.dependencies.iter().map(|d| d.recipe.id.clone()))
-        .filter(|id| {
-            let fetched = list.iter().map(|r| r.id.as_str()).collect::<Vec<&str>>();
-            !fetched.contains(&id.as_str())
-        })
+        .flat_map(|r| r.dependencies.iter().map(|d| d.recipe.id.as_str()))
+        .filter(|id| !fetched.contains(id) && seen.insert(*id))
+        .map(String::from)
         .collect()
 }
 
+/// Fetch a recipe and all the recipes it depends on, directly or not. Each level of dependencies
+/// is fetched concurrently; the result lists the root first, then each level in order of first
+/// appearance, whatever the order the responses came in
 pub async fn recipe_tree(
     url: &str,
     recipe_id: &str,
 ) -> Result<Vec<models::Recipe>, Box<dyn Error>> {
     let root = recipe_get(url, recipe_id).await?;
 
+    let mut requested = HashSet::from([root.id.clone()]);
     let mut recipe_tree = vec![root];
     loop {
         let missing = calc_missing(&recipe_tree);
 
-        if missing.len() == 0 {
+        if missing.is_empty() {
             break;
         }
 
         for required in missing.iter() {
-            recipe_tree.push(recipe_get(url, required).await?);
+            // A server answering with a different recipe than the one requested would have us
+            // loop forever
+            if !requested.insert(required.clone()) {
+                return Err(Box::new(LadleError(format!(
+                    "Server returned inconsistent data for recipe {}",
+                    required
+                ))));
+            }
+        }
+
+        let mut level =
+            stream::iter(missing.iter().map(|id| recipe_get(url, id))).buffered(bulk::CONCURRENCY);
+        while let Some(recipe) = level.next().await {
+            recipe_tree.push(recipe?);
         }
     }
 
     Ok(recipe_tree)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_url_without_prefix() {
+        assert_eq!(
+            normalize_url(&format!("{}/recipes/new", "https://host")),
+            "https://host/recipes/new"
+        );
+        assert_eq!(
+            normalize_url(&format!("{}/recipes/new", "https://host/")),
+            "https://host/recipes/new"
+        );
+    }
+
+    #[test]
+    fn normalize_url_with_prefix() {
+        assert_eq!(
+            normalize_url(&format!("{}/recipes/new", "https://host/api/v1")),
+            "https://host/api/v1/recipes/new"
+        );
+        assert_eq!(
+            normalize_url(&format!("{}/recipes/new", "https://host/api/v1/")),
+            "https://host/api/v1/recipes/new"
+        );
+        assert_eq!(
+            normalize_url(&format!(
+                "{}/recipes/new",
+                "http://localhost:8000/api//v1//"
+            )),
+            "http://localhost:8000/api/v1/recipes/new"
+        );
+    }
+
+    #[test]
+    fn normalize_url_keeps_query() {
+        assert_eq!(
+            normalize_url("https://host/api/v1//recipes?name=http://example.com//a"),
+            "https://host/api/v1/recipes?name=http://example.com//a"
+        );
+        assert_eq!(
+            normalize_url("https://host//ingredients?name=a//b"),
+            "https://host/ingredients?name=a//b"
+        );
+    }
+}
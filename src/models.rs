@@ -1,10 +1,12 @@
 use serde::Deserialize;
 use serde::Serialize;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
 use std::hash::{Hash, Hasher};
 
 /// Element of a recipe listing
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialOrd, Ord, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RecipeIndex {
     pub id: String,
     pub name: String,
@@ -12,6 +14,7 @@ pub struct RecipeIndex {
 
 /// Element of an ingredient listing
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialOrd, Ord, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct IngredientIndex {
     pub id: String,
     pub name: String,
@@ -19,13 +22,24 @@ pub struct IngredientIndex {
 
 /// Element of a label listing
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialOrd, Ord, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct LabelIndex {
     pub id: String,
     pub name: String,
 }
 
+/// Element of an ingredient listing, along with the number of recipes requiring the ingredient
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialOrd, Ord, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IngredientUsage {
+    pub id: String,
+    pub name: String,
+    pub used: usize,
+}
+
 /// Label metadata
 #[derive(Debug, Serialize, Deserialize, Clone, Default, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Label {
     pub id: String,
     pub name: String,
@@ -33,9 +47,14 @@ pub struct Label {
     /// List of recipe indexes tagged with this label
     #[serde(default)]
     pub tagged_recipes: BTreeSet<RecipeIndex>,
+
+    /// Time of the last modification, for servers reporting one. Formatted as RFC 3339
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Classifications {
     pub dairy: bool,
     pub meat: bool,
@@ -45,6 +64,7 @@ pub struct Classifications {
 
 /// Ingredient metadata
 #[derive(Debug, Serialize, Deserialize, Clone, Default, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Ingredient {
     pub id: String,
     pub name: String,
@@ -54,10 +74,15 @@ pub struct Ingredient {
 
     #[serde(default)]
     pub used_in: BTreeSet<RecipeIndex>,
+
+    /// Time of the last modification, for servers reporting one. Formatted as RFC 3339
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
 }
 
 /// Requirement metadata
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialOrd, Ord, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Requirement {
     pub ingredient: IngredientIndex,
     pub quantity: String,
@@ -67,6 +92,7 @@ pub struct Requirement {
 
 /// Dependency metadata
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialOrd, Ord, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Dependency {
     pub recipe: RecipeIndex,
     pub quantity: String,
@@ -76,6 +102,7 @@ pub struct Dependency {
 
 /// Recipe metadata
 #[derive(Debug, Serialize, Deserialize, Clone, Default, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Recipe {
     pub id: String,
     pub name: String,
@@ -89,10 +116,19 @@ pub struct Recipe {
     #[serde(default)]
     pub information: String,
 
+    /// URL of a picture of the recipe, for servers storing one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+
     #[serde(default)]
     pub classifications: Classifications,
 
     /// List of requirements. Contains ingredient indexes
+    ///
+    /// The set is ordered on the ingredient, then the quantity and optional status: requirements
+    /// of the same ingredient with different quantities are all kept, while exact duplicates are
+    /// merged. Such requirements compare equal with `==`, see
+    /// [`Recipe::duplicate_requirement_ingredients`] to detect them.
     #[serde(default)]
     pub requirements: BTreeSet<Requirement>,
 
@@ -103,6 +139,18 @@ pub struct Recipe {
     /// List of tags. Contains label indexes
     #[serde(default)]
     pub tags: BTreeSet<LabelIndex>,
+
+    /// Time of the last modification, for servers reporting one. Formatted as RFC 3339
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// Outcome of a creation request. Servers answer a creation conflicting with existing data with
+/// the existing object, in which case `created` is `false`
+#[derive(Debug, Clone, Default)]
+pub struct Creation<T> {
+    pub object: T,
+    pub created: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -175,6 +223,45 @@ impl PartialEq for RecipeIndex {
     }
 }
 
+impl Classifications {
+    /// Classifications of a mix of both: a flag is set if it is set on either side
+    pub fn union(&self, other: &Classifications) -> Classifications {
+        Classifications {
+            dairy: self.dairy || other.dairy,
+            meat: self.meat || other.meat,
+            gluten: self.gluten || other.gluten,
+            animal_product: self.animal_product || other.animal_product,
+        }
+    }
+}
+
+/// Classifications of a mix of ingredients. An empty mix has no flag set
+pub fn classifications_union<'a>(
+    classifications: impl IntoIterator<Item = &'a Classifications>,
+) -> Classifications {
+    classifications
+        .into_iter()
+        .fold(Classifications::default(), |mix, c| mix.union(c))
+}
+
+impl Recipe {
+    /// Ingredients required more than once by this recipe
+    pub fn duplicate_requirement_ingredients(&self) -> Vec<&IngredientIndex> {
+        let mut seen = HashSet::new();
+        let mut duplicates = vec![];
+
+        for requirement in self.requirements.iter() {
+            if !seen.insert(&requirement.ingredient.id)
+                && !duplicates.contains(&&requirement.ingredient)
+            {
+                duplicates.push(&requirement.ingredient);
+            }
+        }
+
+        duplicates
+    }
+}
+
 impl Hash for Recipe {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
@@ -210,3 +297,140 @@ impl PartialEq for Dependency {
         self.recipe == other.recipe
     }
 }
+
+impl fmt::Display for RecipeIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl fmt::Display for IngredientIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl fmt::Display for LabelIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({} recipes)", self.name, self.tagged_recipes.len())
+    }
+}
+
+impl fmt::Display for Classifications {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags = [
+            (self.dairy, "dairy"),
+            (self.meat, "meat"),
+            (self.gluten, "gluten"),
+            (self.animal_product, "animal product"),
+        ];
+
+        let set = flags
+            .iter()
+            .filter_map(|(set, name)| set.then_some(*name))
+            .collect::<Vec<_>>();
+
+        if set.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", set.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for Ingredient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (used in {} recipes)", self.name, self.used_in.len())
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, {}", self.ingredient, self.quantity)?;
+        if self.optional {
+            write!(f, " (optional)")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, {}", self.recipe, self.quantity)?;
+        if self.optional {
+            write!(f, " (optional)")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Recipe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.author.is_empty() {
+            write!(f, " by {}", self.author)?;
+        }
+        write!(f, " ({} ingredients)", self.requirements.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn union_sets_flags_set_on_either_side() {
+        let dairy = Classifications {
+            dairy: true,
+            animal_product: true,
+            ..Default::default()
+        };
+        let gluten = Classifications {
+            gluten: true,
+            ..Default::default()
+        };
+
+        let mix = Classifications {
+            dairy: true,
+            meat: false,
+            gluten: true,
+            animal_product: true,
+        };
+        assert_eq!(dairy.union(&gluten), mix);
+        assert_eq!(gluten.union(&dairy), mix);
+        assert_eq!(dairy.union(&Classifications::default()), dairy);
+        assert_eq!(dairy.union(&dairy), dairy);
+    }
+
+    #[test]
+    fn classifications_union_folds_every_item() {
+        let meat = Classifications {
+            meat: true,
+            animal_product: true,
+            ..Default::default()
+        };
+        let gluten = Classifications {
+            gluten: true,
+            ..Default::default()
+        };
+        let none = Classifications::default();
+
+        assert_eq!(
+            classifications_union([&none, &meat, &gluten]),
+            Classifications {
+                dairy: false,
+                meat: true,
+                gluten: true,
+                animal_product: true,
+            }
+        );
+        assert_eq!(classifications_union([&none, &none]), none);
+        assert_eq!(classifications_union(std::iter::empty()), none);
+    }
+}
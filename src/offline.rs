@@ -0,0 +1,117 @@
+use crate::models::{
+    Ingredient, IngredientIndex, IngredientUsage, Label, LabelIndex, Recipe, RecipeIndex,
+};
+use serde_json::Value;
+
+/// In-memory replacement for a knife server, answering read requests from the contents of a dump
+pub struct Store {
+    /// Base URL the requests are addressed to
+    pub base: String,
+    pub recipes: Vec<Recipe>,
+    pub ingredients: Vec<Ingredient>,
+    pub labels: Vec<Label>,
+}
+
+fn matches(name: &str, pattern: &str) -> bool {
+    name.to_lowercase().contains(&pattern.to_lowercase())
+}
+
+impl Store {
+    /// Recipes requiring the ingredient with the given id
+    fn uses<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a Recipe> {
+        self.recipes
+            .iter()
+            .filter(move |r| r.requirements.iter().any(|q| q.ingredient.id == id))
+    }
+
+    /// Answer a GET request on `url`. Returns `None` if the URL does not designate any data.
+    pub fn get(&self, url: &str) -> Option<Value> {
+        let path = url.strip_prefix(&self.base)?;
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        let parameter = |key: &str| {
+            query
+                .split('&')
+                .find_map(|p| p.strip_prefix(key)?.strip_prefix('='))
+        };
+        let pattern = parameter("name").unwrap_or("");
+        let parts = path
+            .split('/')
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>();
+
+        let value = match parts[..] {
+            ["recipes"] => serde_json::to_value(
+                self.recipes
+                    .iter()
+                    .filter(|r| matches(&r.name, pattern))
+                    .map(|r| RecipeIndex {
+                        id: r.id.clone(),
+                        name: r.name.clone(),
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            ["recipes", id] => serde_json::to_value(self.recipes.iter().find(|r| r.id == id)?),
+            ["recipes", id, "requirements"] => {
+                serde_json::to_value(&self.recipes.iter().find(|r| r.id == id)?.requirements)
+            }
+            ["ingredients"] if parameter("fields") == Some("used") => serde_json::to_value(
+                self.ingredients
+                    .iter()
+                    .filter(|i| matches(&i.name, pattern))
+                    .map(|i| IngredientUsage {
+                        id: i.id.clone(),
+                        name: i.name.clone(),
+                        used: self.uses(&i.id).count(),
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            ["ingredients"] => serde_json::to_value(
+                self.ingredients
+                    .iter()
+                    .filter(|i| matches(&i.name, pattern))
+                    .map(|i| IngredientIndex {
+                        id: i.id.clone(),
+                        name: i.name.clone(),
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            ["ingredients", id] => {
+                let mut ingredient = self.ingredients.iter().find(|i| i.id == id)?.clone();
+                ingredient.used_in = self
+                    .uses(id)
+                    .map(|r| RecipeIndex {
+                        id: r.id.clone(),
+                        name: r.name.clone(),
+                    })
+                    .collect();
+                serde_json::to_value(ingredient)
+            }
+            ["labels"] => serde_json::to_value(
+                self.labels
+                    .iter()
+                    .filter(|l| matches(&l.name, pattern))
+                    .map(|l| LabelIndex {
+                        id: l.id.clone(),
+                        name: l.name.clone(),
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            ["labels", id] => {
+                let mut label = self.labels.iter().find(|l| l.id == id)?.clone();
+                label.tagged_recipes = self
+                    .recipes
+                    .iter()
+                    .filter(|r| r.tags.iter().any(|t| t.id == id))
+                    .map(|r| RecipeIndex {
+                        id: r.id.clone(),
+                        name: r.name.clone(),
+                    })
+                    .collect();
+                serde_json::to_value(label)
+            }
+            _ => return None,
+        };
+
+        value.ok()
+    }
+}
@@ -0,0 +1,281 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Parsed form of a requirement or dependency quantity, such as `200 g`, `1,5 l`, `½ cup` or
+/// `2-3 eggs`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Quantity {
+    pub amount: f64,
+
+    /// Unit following the amount, empty for unit-less quantities
+    pub unit: String,
+}
+
+/// Known units, along with the canonical unit of their dimension and the factor converting to it
+const CONVERSIONS: &[(&str, &str, f64)] = &[
+    ("mg", "g", 0.001),
+    ("g", "g", 1.0),
+    ("kg", "g", 1000.0),
+    ("ml", "ml", 1.0),
+    ("cl", "ml", 10.0),
+    ("dl", "ml", 100.0),
+    ("l", "ml", 1000.0),
+];
+
+impl Quantity {
+    /// Express the quantity in the canonical unit of its dimension. Units are matched ignoring
+    /// case. Quantities of unknown units are returned untouched
+    pub fn canonical(&self) -> Quantity {
+        let unit = self.unit.to_lowercase();
+        match CONVERSIONS.iter().find(|(known, _, _)| *known == unit) {
+            Some((_, canonical, factor)) => Quantity {
+                amount: self.amount * factor,
+                unit: canonical.to_string(),
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Express a canonical quantity in the larger unit of its dimension when it is large enough,
+    /// as in `1.5 kg` rather than `1500 g`
+    pub fn humanize(&self) -> Quantity {
+        match self.unit.as_str() {
+            "g" if self.amount >= 1000.0 => Quantity {
+                amount: self.amount / 1000.0,
+                unit: String::from("kg"),
+            },
+            "ml" if self.amount >= 1000.0 => Quantity {
+                amount: self.amount / 1000.0,
+                unit: String::from("l"),
+            },
+            _ => self.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct QuantityError(String);
+
+impl fmt::Display for QuantityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse quantity: `{}`", self.0)
+    }
+}
+
+impl Error for QuantityError {}
+
+/// Value of a unicode vulgar fraction character
+fn vulgar_fraction(c: char) -> Option<f64> {
+    match c {
+        '½' => Some(1.0 / 2.0),
+        '⅓' => Some(1.0 / 3.0),
+        '⅔' => Some(2.0 / 3.0),
+        '¼' => Some(1.0 / 4.0),
+        '¾' => Some(3.0 / 4.0),
+        '⅕' => Some(1.0 / 5.0),
+        '⅖' => Some(2.0 / 5.0),
+        '⅗' => Some(3.0 / 5.0),
+        '⅘' => Some(4.0 / 5.0),
+        '⅙' => Some(1.0 / 6.0),
+        '⅚' => Some(5.0 / 6.0),
+        '⅛' => Some(1.0 / 8.0),
+        '⅜' => Some(3.0 / 8.0),
+        '⅝' => Some(5.0 / 8.0),
+        '⅞' => Some(7.0 / 8.0),
+        _ => None,
+    }
+}
+
+/// Parse a single number: a decimal using a dot or a comma, an ASCII fraction such as `1/2`, or an
+/// optional whole part followed by a vulgar fraction such as `1½`
+fn parse_number(s: &str) -> Option<f64> {
+    if let Some((numerator, denominator)) = s.split_once('/') {
+        let denominator = parse_number(denominator)?;
+        if denominator == 0.0 {
+            return None;
+        }
+        return parse_number(numerator).map(|n| n / denominator);
+    }
+
+    let mut chars = s.chars();
+    match chars.next_back().and_then(vulgar_fraction) {
+        Some(fraction) if chars.as_str().is_empty() => Some(fraction),
+        Some(fraction) => parse_number(chars.as_str()).map(|whole| whole + fraction),
+        None => parse_decimal(s),
+    }
+}
+
+/// Parse a decimal number. A comma splitting the whole part in groups of three digits is read as a
+/// thousands separator, as in `1,000`, and as a decimal separator otherwise, as in `1,5` or `0,250`
+fn parse_decimal(s: &str) -> Option<f64> {
+    let whole = s.split('.').next().unwrap_or_default();
+    let mut groups = whole.split(',');
+    let leading = groups.next().unwrap_or_default();
+    let groups = groups.collect::<Vec<_>>();
+
+    let is_digits = |group: &str| group.chars().all(|c| c.is_ascii_digit());
+    let grouped = !groups.is_empty()
+        && (1..=3).contains(&leading.len())
+        && !leading.starts_with('0')
+        && is_digits(leading)
+        && groups.iter().all(|g| g.len() == 3 && is_digits(g));
+
+    if grouped {
+        s.replace(',', "").parse::<f64>().ok()
+    } else {
+        s.replace(',', ".").parse::<f64>().ok()
+    }
+}
+
+/// Parse an amount made of whitespace separated numbers, summed to support mixed numbers such as
+/// `1 1/2`. A range such as `2-3` is read as its midpoint
+fn parse_amount(s: &str) -> Option<f64> {
+    if let Some((low, high)) = s.split_once('-') {
+        return Some((parse_amount(low)? + parse_amount(high)?) / 2.0);
+    }
+
+    let mut numbers = s.split_whitespace().peekable();
+    numbers.peek()?;
+    numbers.map(parse_number).sum()
+}
+
+impl FromStr for Quantity {
+    type Err = QuantityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let split = trimmed
+            .find(|c: char| {
+                !(c.is_ascii_digit()
+                    || matches!(c, '.' | ',' | '/' | '-' | ' ')
+                    || vulgar_fraction(c).is_some())
+            })
+            .unwrap_or(trimmed.len());
+        let (amount, unit) = trimmed.split_at(split);
+
+        match parse_amount(amount) {
+            Some(amount) => Ok(Quantity {
+                amount,
+                unit: unit.trim().to_string(),
+            }),
+            None => Err(QuantityError(s.to_string())),
+        }
+    }
+}
+
+/// Decimal places kept when displaying an amount
+const DISPLAY_PRECISION: usize = 3;
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rounded = format!("{:.*}", DISPLAY_PRECISION, self.amount);
+        let amount = match rounded.trim_end_matches('0').trim_end_matches('.') {
+            "-0" => "0",
+            trimmed => trimmed,
+        };
+
+        if self.unit.is_empty() {
+            write!(f, "{}", amount)
+        } else {
+            write!(f, "{} {}", amount, self.unit)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quantity(amount: f64, unit: &str) -> Quantity {
+        Quantity {
+            amount,
+            unit: unit.to_string(),
+        }
+    }
+
+    #[test]
+    fn vulgar_fractions() {
+        assert_eq!("½ cup".parse::<Quantity>().unwrap(), quantity(0.5, "cup"));
+        assert_eq!("1½".parse::<Quantity>().unwrap(), quantity(1.5, ""));
+        assert_eq!("2¼ kg".parse::<Quantity>().unwrap(), quantity(2.25, "kg"));
+    }
+
+    #[test]
+    fn ascii_fractions() {
+        assert_eq!("1/2".parse::<Quantity>().unwrap(), quantity(0.5, ""));
+        assert_eq!("1 1/2".parse::<Quantity>().unwrap(), quantity(1.5, ""));
+        assert_eq!("3/4 l".parse::<Quantity>().unwrap(), quantity(0.75, "l"));
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(
+            "2-3 eggs".parse::<Quantity>().unwrap(),
+            quantity(2.5, "eggs")
+        );
+        assert_eq!(
+            "1/2-1 cup".parse::<Quantity>().unwrap(),
+            quantity(0.75, "cup")
+        );
+    }
+
+    #[test]
+    fn decimals() {
+        assert_eq!("1,5 l".parse::<Quantity>().unwrap(), quantity(1.5, "l"));
+        assert_eq!("0.25 kg".parse::<Quantity>().unwrap(), quantity(0.25, "kg"));
+        assert_eq!("200g".parse::<Quantity>().unwrap(), quantity(200.0, "g"));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(quantity(2.0 / 3.0, "cup").to_string(), "0.667 cup");
+        assert_eq!(quantity(0.1 + 0.2, "l").to_string(), "0.3 l");
+        assert_eq!(quantity(1500.0, "g").to_string(), "1500 g");
+        assert_eq!(quantity(2.5, "").to_string(), "2.5");
+        assert_eq!(quantity(0.0001, "g").to_string(), "0 g");
+    }
+
+    #[test]
+    fn thousands_separator() {
+        assert_eq!(
+            "1,000 g".parse::<Quantity>().unwrap(),
+            quantity(1000.0, "g")
+        );
+        assert_eq!(
+            "12,500,000 mg".parse::<Quantity>().unwrap(),
+            quantity(12500000.0, "mg")
+        );
+        assert_eq!("1,000.5".parse::<Quantity>().unwrap(), quantity(1000.5, ""));
+        assert_eq!(
+            "0,250 kg".parse::<Quantity>().unwrap(),
+            quantity(0.25, "kg")
+        );
+        assert_eq!("1,25 l".parse::<Quantity>().unwrap(), quantity(1.25, "l"));
+    }
+
+    #[test]
+    fn canonical_ignores_case() {
+        assert_eq!(quantity(1.0, "L").canonical(), quantity(1000.0, "ml"));
+        assert_eq!(quantity(1.0, "Kg").canonical(), quantity(1000.0, "g"));
+        assert_eq!(quantity(2.0, "Eggs").canonical(), quantity(2.0, "Eggs"));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert!("1/0".parse::<Quantity>().is_err());
+        assert!("1/0 cup".parse::<Quantity>().is_err());
+    }
+
+    #[test]
+    fn free_text() {
+        assert_eq!(
+            "2 eggs, beaten".parse::<Quantity>().unwrap(),
+            quantity(2.0, "eggs, beaten")
+        );
+
+        let error = "une pincée".parse::<Quantity>().unwrap_err();
+        assert_eq!(error.to_string(), "Failed to parse quantity: `une pincée`");
+        assert!("".parse::<Quantity>().is_err());
+    }
+}